@@ -0,0 +1,487 @@
+//! [`Client::broadcast_and_confirm`]: broadcast a transaction and wait for it.
+//!
+//! The transaction is parsed locally first, which gives its txid (checked
+//! against the one the server returns) and the outpoints it spends. It is
+//! then polled until it reaches the [`BroadcastPolicy`]'s target. Whenever it
+//! can't be found, its inputs are checked with `tx/:txid/outspend/:vout`: an
+//! input spent by some other transaction means it was replaced (RBF) or
+//! double spent, and that transaction's txid is returned.
+//!
+//! A rejected broadcast comes back as [`Error::Http`] whose body is bitcoind's
+//! RPC error; [`Error::broadcast_rejection`] parses it into an
+//! [`RpcRejection`] with a [`BroadcastRejection`] category.
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use crate::rawtx::RawTx;
+use crate::{Client, Error};
+
+/// A transaction output reference: the `vout`-th output of `txid`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OutPoint {
+    /// The funding transaction's ID.
+    pub txid: String,
+    /// The output index.
+    pub vout: u32,
+}
+
+impl fmt::Display for OutPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.txid, self.vout)
+    }
+}
+
+/// Why the node refused a transaction, grouped by what the caller can do
+/// about it. Parsed from bitcoind's reject reason (see
+/// [`Error::broadcast_rejection`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BroadcastRejection {
+    /// The fee rate is below the node's relay or mempool minimum, or too low
+    /// to replace the transaction it conflicts with. Fee-bump and retry.
+    FeeTooLow,
+    /// An input doesn't exist or is already spent: a parent is unconfirmed
+    /// and unknown to the node, or the coins are gone.
+    MissingInputs,
+    /// The transaction is already confirmed, or already in the mempool;
+    /// there is nothing to broadcast.
+    AlreadyInChain,
+    /// An input is spent by a mempool transaction that can't be replaced.
+    MempoolConflict,
+    /// The lock time or a relative lock time (BIP 68) is not yet satisfied.
+    NonFinal,
+    /// An output is below the dust threshold.
+    Dust,
+    /// A signature or script failed to verify.
+    ScriptVerifyFailed,
+    /// Too many unconfirmed ancestors or descendants in the mempool; wait
+    /// for some to confirm.
+    TooLongMempoolChain,
+    /// Anything else; see the message.
+    Unknown,
+}
+
+impl BroadcastRejection {
+    /// Categorizes a bitcoind reject reason such as `min relay fee not met`
+    /// or `bad-txns-inputs-missingorspent`.
+    pub fn from_reason(reason: &str) -> Self {
+        let reason = reason.to_ascii_lowercase();
+        let has = |needle: &str| reason.contains(needle);
+        if has("fee not met") || has("insufficient fee") || has("mempool full") {
+            Self::FeeTooLow
+        } else if has("too-long-mempool-chain") {
+            Self::TooLongMempoolChain
+        } else if has("missingorspent") || has("missing-inputs") || has("missing inputs") {
+            Self::MissingInputs
+        } else if has("already in block chain")
+            || has("already in utxo set")
+            || has("txn-already-known")
+            || has("txn-already-in-mempool")
+        {
+            Self::AlreadyInChain
+        } else if has("txn-mempool-conflict") {
+            Self::MempoolConflict
+        } else if has("non-final") || has("non-bip68-final") {
+            Self::NonFinal
+        } else if has("dust") {
+            Self::Dust
+        } else if has("script-verify-flag") || has("script verify") {
+            Self::ScriptVerifyFailed
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Whether rebroadcasting with a higher fee might be accepted.
+    pub fn fee_bump_may_help(self) -> bool {
+        matches!(self, Self::FeeTooLow | Self::MempoolConflict)
+    }
+}
+
+impl fmt::Display for BroadcastRejection {
+    /// A short message suitable for showing to a user.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::FeeTooLow => "fee too low",
+            Self::MissingInputs => "inputs missing or already spent",
+            Self::AlreadyInChain => "transaction already known",
+            Self::MempoolConflict => "conflicts with a transaction in the mempool",
+            Self::NonFinal => "transaction is not final yet",
+            Self::Dust => "an output is below the dust limit",
+            Self::ScriptVerifyFailed => "signature or script verification failed",
+            Self::TooLongMempoolChain => "too many unconfirmed ancestors or descendants",
+            Self::Unknown => "rejected",
+        })
+    }
+}
+
+/// A broadcast rejection parsed from the server's error body, e.g.
+/// `sendrawtransaction RPC error: {"code":-26,"message":"min relay fee not met"}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcRejection {
+    /// The category of [`Self::message`].
+    pub reason: BroadcastRejection,
+    /// The bitcoind RPC error code (`-26` rejected, `-25` verify error,
+    /// `-27` already in chain), when the body carried one.
+    pub code: Option<i64>,
+    /// The reject reason: the RPC `message`, or the whole body if it had no
+    /// JSON part.
+    pub message: String,
+}
+
+impl RpcRejection {
+    /// Parses a broadcast error body.
+    pub fn parse(body: &str) -> Self {
+        #[derive(Deserialize)]
+        struct RpcError {
+            code: Option<i64>,
+            message: String,
+        }
+
+        let rpc = body
+            .find('{')
+            .and_then(|start| serde_json::from_str::<RpcError>(body[start..].trim()).ok());
+        let (code, message) = match rpc {
+            Some(rpc) => (rpc.code, rpc.message),
+            None => (None, body.trim().to_string()),
+        };
+        let mut reason = BroadcastRejection::from_reason(&message);
+        if reason == BroadcastRejection::Unknown && code == Some(-27) {
+            reason = BroadcastRejection::AlreadyInChain;
+        }
+        Self {
+            reason,
+            code,
+            message,
+        }
+    }
+}
+
+/// How far [`Client::broadcast_and_confirm`] follows a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmTarget {
+    /// Until the server has it, in the mempool or a block.
+    Propagated,
+    /// Until it has this many confirmations (`0` is treated as `1`).
+    Confirmations(u32),
+}
+
+/// How [`Client::broadcast_and_confirm`] waits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastPolicy {
+    /// When to stop waiting successfully.
+    pub target: ConfirmTarget,
+    /// Delay between status checks.
+    pub poll_interval: Duration,
+    /// How long to wait in total before giving up with
+    /// [`BroadcastOutcome::TimedOut`].
+    pub timeout: Duration,
+}
+
+impl Default for BroadcastPolicy {
+    /// One confirmation, checked every 30 s, for up to 2 hours.
+    fn default() -> Self {
+        Self {
+            target: ConfirmTarget::Confirmations(1),
+            poll_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(2 * 60 * 60),
+        }
+    }
+}
+
+impl BroadcastPolicy {
+    /// The default policy, but done once the server has the transaction.
+    #[must_use]
+    pub fn propagated() -> Self {
+        Self {
+            target: ConfirmTarget::Propagated,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy, but waiting for `confirmations`.
+    #[must_use]
+    pub fn confirmations(confirmations: u32) -> Self {
+        Self {
+            target: ConfirmTarget::Confirmations(confirmations),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the delay between status checks.
+    #[must_use]
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the overall timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+/// How a [`Client::broadcast_and_confirm`] call ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[must_use]
+pub enum BroadcastOutcome {
+    /// The server has the transaction, unconfirmed
+    /// ([`ConfirmTarget::Propagated`] only).
+    Propagated {
+        /// The transaction ID.
+        txid: String,
+    },
+    /// The transaction reached the target depth.
+    Confirmed {
+        /// The transaction ID.
+        txid: String,
+        /// The including block's height.
+        height: u64,
+        /// The including block's hash.
+        block_hash: String,
+        /// The confirmation count when this was noticed.
+        confirmations: u32,
+    },
+    /// Another transaction spent one of this transaction's inputs: a
+    /// replacement or a double spend. This transaction can no longer confirm
+    /// unless that one is itself evicted.
+    Conflicted {
+        /// The transaction ID.
+        txid: String,
+        /// The transaction that spent `outpoint`.
+        conflicting_txid: String,
+        /// The contested input.
+        outpoint: OutPoint,
+    },
+    /// The policy's timeout elapsed first.
+    TimedOut {
+        /// The transaction ID.
+        txid: String,
+        /// Whether the server had the transaction at the last check.
+        seen: bool,
+    },
+}
+
+impl BroadcastOutcome {
+    /// The broadcast transaction's ID.
+    pub fn txid(&self) -> &str {
+        match self {
+            Self::Propagated { txid }
+            | Self::Confirmed { txid, .. }
+            | Self::Conflicted { txid, .. }
+            | Self::TimedOut { txid, .. } => txid,
+        }
+    }
+}
+
+impl Client {
+    /// Broadcasts `tx_hex` and waits for it as `policy` says. See the
+    /// [module docs](self).
+    ///
+    /// If the broadcast fails, the inputs are checked, so a rejection caused
+    /// by a conflicting transaction (including a replacement refused for its
+    /// fee) comes back as [`BroadcastOutcome::Conflicted`] rather than an
+    /// error. A transaction the node already has
+    /// ([`BroadcastRejection::AlreadyInChain`]) is watched as if the
+    /// broadcast had succeeded.
+    ///
+    /// ```no_run
+    /// # async fn demo(client: esplora_rs::Client, tx_hex: &str) -> Result<(), esplora_rs::Error> {
+    /// use esplora_rs::{BroadcastOutcome, BroadcastPolicy};
+    ///
+    /// match client.broadcast_and_confirm(tx_hex, BroadcastPolicy::confirmations(2)).await? {
+    ///     BroadcastOutcome::Confirmed { txid, height, .. } => println!("{txid} in {height}"),
+    ///     BroadcastOutcome::Conflicted { conflicting_txid, .. } => {
+    ///         println!("replaced by {conflicting_txid}")
+    ///     }
+    ///     other => println!("{other:?}"),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if `tx_hex` isn't a Bitcoin transaction
+    /// (nothing is sent), [`Error::TxidMismatch`] if the server reports a
+    /// different txid than the one computed locally, the broadcast error if
+    /// it was rejected for a reason other than a conflict, and any other
+    /// request error.
+    pub async fn broadcast_and_confirm(
+        &self,
+        tx_hex: &str,
+        policy: BroadcastPolicy,
+    ) -> Result<BroadcastOutcome, Error> {
+        let tx = RawTx::from_hex(tx_hex)?;
+        let deadline = Instant::now() + policy.timeout;
+
+        let returned = match self.broadcast_tx(tx_hex).await {
+            Ok(txid) => txid,
+            Err(err)
+                if err
+                    .broadcast_rejection()
+                    .is_some_and(|r| r.reason == BroadcastRejection::AlreadyInChain) =>
+            {
+                debug!(target: "esplora_rs", "tx {} already known; watching it", tx.txid);
+                tx.txid.clone()
+            }
+            Err(err) => {
+                return match self.find_conflict(&tx).await {
+                    Ok(Some(conflict)) => Ok(conflict),
+                    _ => Err(err),
+                };
+            }
+        };
+        if !returned.eq_ignore_ascii_case(&tx.txid) {
+            return Err(Error::TxidMismatch {
+                expected: tx.txid,
+                actual: returned,
+            });
+        }
+
+        let min_confirmations = match policy.target {
+            ConfirmTarget::Propagated => 0,
+            ConfirmTarget::Confirmations(n) => n.max(1),
+        };
+        let chain = self.following_chain();
+        let mut seen = false;
+        loop {
+            match chain.get_tx(&tx.txid).await {
+                Ok(found) => {
+                    seen = true;
+                    let status = found.status;
+                    match (status.block_height, status.block_hash) {
+                        (Some(height), Some(block_hash)) if status.confirmed => {
+                            let tip = self.get_tip_height().await?;
+                            let confirmations =
+                                u32::try_from(tip.saturating_sub(height) + 1).unwrap_or(u32::MAX);
+                            if confirmations >= min_confirmations {
+                                return Ok(BroadcastOutcome::Confirmed {
+                                    txid: tx.txid,
+                                    height,
+                                    block_hash,
+                                    confirmations,
+                                });
+                            }
+                        }
+                        _ if min_confirmations == 0 => {
+                            return Ok(BroadcastOutcome::Propagated { txid: tx.txid });
+                        }
+                        _ => {}
+                    }
+                }
+                Err(err) if err.status() == Some(404) => {
+                    if seen {
+                        warn!(target: "esplora_rs", "tx {} vanished; checking its inputs", tx.txid);
+                    }
+                    seen = false;
+                    if let Some(conflict) = self.find_conflict(&tx).await? {
+                        return Ok(conflict);
+                    }
+                }
+                Err(err) => return Err(err),
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Ok(BroadcastOutcome::TimedOut {
+                    txid: tx.txid,
+                    seen,
+                });
+            }
+            tokio::time::sleep(policy.poll_interval.min(deadline - now)).await;
+        }
+    }
+
+    /// Looks for an input of `tx` spent by a different transaction. A `404`
+    /// for an input (its funding transaction unknown to the server) is not a
+    /// conflict.
+    async fn find_conflict(&self, tx: &RawTx) -> Result<Option<BroadcastOutcome>, Error> {
+        let outspends = self
+            .batch(tx.inputs.clone(), |outpoint| async move {
+                self.get_outspend(&outpoint.txid, outpoint.vout).await
+            })
+            .await;
+        for (outpoint, outspend) in tx.inputs.iter().zip(outspends) {
+            let outspend = match outspend {
+                Ok(outspend) => outspend,
+                Err(err) if err.status() == Some(404) => continue,
+                Err(err) => return Err(err),
+            };
+            let Some(spender) = outspend.txid.filter(|_| outspend.spent) else {
+                continue;
+            };
+            if !spender.eq_ignore_ascii_case(&tx.txid) {
+                debug!(target: "esplora_rs", "{} of tx {} spent by {}", outpoint, tx.txid, spender);
+                return Ok(Some(BroadcastOutcome::Conflicted {
+                    txid: tx.txid.clone(),
+                    conflicting_txid: spender,
+                    outpoint: outpoint.clone(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_rpc_error_bodies() {
+        let rejection = RpcRejection::parse(
+            r#"sendrawtransaction RPC error: {"code":-26,"message":"min relay fee not met, 100 < 141"}"#,
+        );
+        assert_eq!(rejection.reason, BroadcastRejection::FeeTooLow);
+        assert_eq!(rejection.code, Some(-26));
+        assert_eq!(rejection.message, "min relay fee not met, 100 < 141");
+        assert!(rejection.reason.fee_bump_may_help());
+
+        let rejection = RpcRejection::parse("bad-txns-inputs-missingorspent\n");
+        assert_eq!(rejection.reason, BroadcastRejection::MissingInputs);
+        assert_eq!(rejection.code, None);
+        assert_eq!(rejection.message, "bad-txns-inputs-missingorspent");
+
+        let rejection = RpcRejection::parse(
+            r#"sendrawtransaction RPC error: {"code":-27,"message":"Transaction outputs already in utxo set"}"#,
+        );
+        assert_eq!(rejection.reason, BroadcastRejection::AlreadyInChain);
+    }
+
+    #[test]
+    fn categorizes_reject_reasons() {
+        let cases = [
+            ("mempool min fee not met", BroadcastRejection::FeeTooLow),
+            (
+                "insufficient fee, rejecting replacement abcd",
+                BroadcastRejection::FeeTooLow,
+            ),
+            ("txn-mempool-conflict", BroadcastRejection::MempoolConflict),
+            ("txn-already-in-mempool", BroadcastRejection::AlreadyInChain),
+            ("non-BIP68-final", BroadcastRejection::NonFinal),
+            ("non-final", BroadcastRejection::NonFinal),
+            ("dust", BroadcastRejection::Dust),
+            (
+                "mandatory-script-verify-flag-failed (Signature must be zero for failed CHECK(MULTI)SIG operation)",
+                BroadcastRejection::ScriptVerifyFailed,
+            ),
+            (
+                "too-long-mempool-chain, too many unconfirmed ancestors [limit: 25]",
+                BroadcastRejection::TooLongMempoolChain,
+            ),
+            ("bad-txns-vout-negative", BroadcastRejection::Unknown),
+        ];
+        for (reason, expected) in cases {
+            assert_eq!(
+                BroadcastRejection::from_reason(reason),
+                expected,
+                "{reason}"
+            );
+        }
+    }
+}
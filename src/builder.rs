@@ -0,0 +1,330 @@
+//! [`ClientBuilder`]: transport configuration for [`Client`].
+//!
+//! [`Client::new_public`] and [`Client::new`] are thin wrappers over this
+//! builder with its defaults; use [`Client::builder`] directly to set
+//! timeouts, a `User-Agent`, extra headers, the HTTP version, or to inject a
+//! pre-built `reqwest::Client`.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client as ReqwestClient;
+use url::Url;
+
+use crate::auth::TokenProvider;
+use crate::batch::DEFAULT_BATCH_CONCURRENCY;
+use crate::cache::{CacheConfig, ResponseCache};
+use crate::disk_cache::{DiskCache, DiskCacheConfig};
+use crate::failover::{Backend, Backends, DEFAULT_COOLDOWN};
+use crate::ratelimit::RateLimiter;
+use crate::usage::{CreditAlert, UsageTracker};
+use crate::{ensure_base_slash, Client, Error, RateLimit, RetryPolicy};
+
+/// Default total per-request timeout (connect + send + full body).
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default TCP/TLS connect timeout.
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Default `User-Agent` sent with every request (`esplora-rs/<version>`).
+pub const DEFAULT_USER_AGENT: &str = concat!("esplora-rs/", env!("CARGO_PKG_VERSION"));
+
+/// Which HTTP protocol version the built client speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HttpVersion {
+    /// HTTP/1.1 only. The default: some servers/CDNs mishandle HTTP/2 POSTs
+    /// (e.g. `broadcast_tx`).
+    #[default]
+    Http1Only,
+    /// HTTP/2 with prior knowledge (no HTTP/1.1 upgrade or ALPN fallback).
+    Http2PriorKnowledge,
+    /// Let `reqwest` negotiate (ALPN over TLS, HTTP/1.1 otherwise).
+    Negotiate,
+}
+
+/// Builds a [`Client`]. Created by [`Client::builder`].
+///
+/// ```no_run
+/// use std::time::Duration;
+/// use esplora_rs::Client;
+///
+/// # fn main() -> Result<(), esplora_rs::Error> {
+/// let client = Client::builder("https://blockstream.info/testnet/api/")
+///     .timeout(Duration::from_secs(20))
+///     .read_timeout(Duration::from_secs(5))
+///     .user_agent("my-wallet/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    base_url: String,
+    auth: Option<Arc<dyn TokenProvider>>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    user_agent: String,
+    headers: Vec<(String, String)>,
+    http_version: HttpVersion,
+    http_client: Option<ReqwestClient>,
+    retry: RetryPolicy,
+    fallbacks: Vec<(String, Arc<dyn TokenProvider>)>,
+    failover_cooldown: Duration,
+    rate_limit: Option<RateLimit>,
+    batch_concurrency: usize,
+    cache: Option<CacheConfig>,
+    disk_cache: Option<DiskCacheConfig>,
+    credit_alert: Option<CreditAlert>,
+}
+
+impl ClientBuilder {
+    pub(crate) fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            auth: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: None,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: Vec::new(),
+            http_version: HttpVersion::default(),
+            http_client: None,
+            retry: RetryPolicy::disabled(),
+            fallbacks: Vec::new(),
+            failover_cooldown: DEFAULT_COOLDOWN,
+            rate_limit: None,
+            batch_concurrency: DEFAULT_BATCH_CONCURRENCY,
+            cache: None,
+            disk_cache: None,
+            credit_alert: None,
+        }
+    }
+
+    /// Sets the [`TokenProvider`] consulted for every request's auth
+    /// headers: an OAuth [`crate::Auth`], a [`crate::StaticHeader`], or your
+    /// own. Defaults to none (no `Authorization` header).
+    #[must_use]
+    pub fn auth(mut self, auth: impl TokenProvider + 'static) -> Self {
+        self.auth = Some(Arc::new(auth));
+        self
+    }
+
+    /// Sets the total per-request timeout, covering connect, send and reading
+    /// the whole body. Defaults to [`DEFAULT_TIMEOUT`].
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Removes the total per-request timeout. Only do this together with a
+    /// [`Self::read_timeout`], or a stalled endpoint can hang a call forever.
+    #[must_use]
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Sets the connect timeout. Defaults to [`DEFAULT_CONNECT_TIMEOUT`].
+    #[must_use]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets a read timeout: the longest the client waits for the response
+    /// headers, and then separately for the response body. Unset by default.
+    /// Expiry surfaces as [`Error::Timeout`].
+    #[must_use]
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the `User-Agent` header. Defaults to [`DEFAULT_USER_AGENT`].
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Adds a header sent with every request. Invalid names or values are
+    /// reported by [`Self::build`].
+    #[must_use]
+    pub fn default_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Selects the HTTP protocol version. Defaults to [`HttpVersion::Http1Only`].
+    #[must_use]
+    pub fn http_version(mut self, version: HttpVersion) -> Self {
+        self.http_version = version;
+        self
+    }
+
+    /// Uses a pre-built `reqwest::Client` instead of building one. Its own
+    /// configuration is used as-is: the builder's `timeout`, `connect_timeout`,
+    /// `user_agent`, `default_header` and `http_version` settings are ignored.
+    /// [`Self::read_timeout`] still applies.
+    #[must_use]
+    pub fn http_client(mut self, client: ReqwestClient) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Sets the retry policy for failed requests. Defaults to
+    /// [`RetryPolicy::disabled`]; [`RetryPolicy::default`] is a sensible
+    /// starting point.
+    #[must_use]
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Adds a fallback backend, tried (in the order added) when the primary
+    /// `base_url` — and any earlier fallback — fails with a connect error,
+    /// timeout, `5xx` or `429`. Each backend carries its own
+    /// [`TokenProvider`] (pass [`crate::Auth::new_public`] for none). See
+    /// [`crate::failover`].
+    #[must_use]
+    pub fn fallback(mut self, base_url: &str, auth: impl TokenProvider + 'static) -> Self {
+        self.fallbacks.push((base_url.to_string(), Arc::new(auth)));
+        self
+    }
+
+    /// Sets how long a failing backend is skipped before it is tried again.
+    /// Defaults to [`DEFAULT_COOLDOWN`].
+    #[must_use]
+    pub fn failover_cooldown(mut self, cooldown: Duration) -> Self {
+        self.failover_cooldown = cooldown;
+        self
+    }
+
+    /// Paces requests with a token bucket shared by all clones of the built
+    /// client, tightening automatically after `429`s. Off by default. See
+    /// [`crate::ratelimit`]. The rate must be finite and positive, or
+    /// [`Self::build`] fails.
+    #[must_use]
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Caps how many requests a batch method (e.g. [`Client::get_txs`]) has in
+    /// flight at once. Defaults to [`DEFAULT_BATCH_CONCURRENCY`]; `0` is
+    /// treated as `1`.
+    #[must_use]
+    pub fn batch_concurrency(mut self, limit: usize) -> Self {
+        self.batch_concurrency = limit.max(1);
+        self
+    }
+
+    /// Caches immutable chain data (blocks by hash, deeply confirmed
+    /// transactions) in memory, shared by all clones of the built client.
+    /// Off by default. See [`crate::cache`].
+    #[must_use]
+    pub fn cache(mut self, config: CacheConfig) -> Self {
+        self.cache = Some(config);
+        self
+    }
+
+    /// Persists blocks and deeply confirmed transactions on disk so they
+    /// survive restarts, and optionally serves only from the caches
+    /// ([`DiskCacheConfig::offline`]). Checked behind the memory
+    /// [`Self::cache`] when both are set. See [`crate::disk_cache`].
+    #[must_use]
+    pub fn disk_cache(mut self, config: DiskCacheConfig) -> Self {
+        self.disk_cache = Some(config);
+        self
+    }
+
+    /// Warns (and calls the alert's callback, if set) when a response reports
+    /// fewer remaining credits than the alert's threshold. Usage is tracked
+    /// either way; see [`crate::usage`].
+    #[must_use]
+    pub fn credit_alert(mut self, alert: CreditAlert) -> Self {
+        self.credit_alert = Some(alert);
+        self
+    }
+
+    /// Builds the [`Client`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Url`] if a base URL is invalid, [`Error::Config`] if
+    /// the `User-Agent` or a default header is not a valid HTTP header, the
+    /// rate limit isn't a positive rate, or the disk cache directory can't be
+    /// opened, or
+    /// [`Error::Reqwest`] if the HTTP client cannot be built.
+    pub fn build(self) -> Result<Client, Error> {
+        if let Some(limit) = &self.rate_limit {
+            limit.validate()?;
+        }
+        let max_hold = self.retry.max_delay;
+        let mut backends = vec![Backend {
+            base_url: Url::parse(&ensure_base_slash(&self.base_url))?,
+            auth: self.auth,
+        }];
+        for (base_url, auth) in self.fallbacks {
+            backends.push(Backend {
+                base_url: Url::parse(&ensure_base_slash(&base_url))?,
+                auth: Some(auth),
+            });
+        }
+
+        let http_client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let mut headers = HeaderMap::new();
+                for (name, value) in &self.headers {
+                    let name = HeaderName::from_bytes(name.as_bytes())
+                        .map_err(|e| Error::Config(format!("invalid header name {name:?}: {e}")))?;
+                    let value = HeaderValue::from_str(value).map_err(|e| {
+                        Error::Config(format!("invalid value for header {name}: {e}"))
+                    })?;
+                    headers.append(name, value);
+                }
+                let user_agent = HeaderValue::from_str(&self.user_agent)
+                    .map_err(|e| Error::Config(format!("invalid User-Agent: {e}")))?;
+
+                let mut builder = ReqwestClient::builder()
+                    .user_agent(user_agent)
+                    .default_headers(headers);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                builder = match self.http_version {
+                    HttpVersion::Http1Only => builder.http1_only(),
+                    HttpVersion::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+                    HttpVersion::Negotiate => builder,
+                };
+                builder.build().map_err(Error::Reqwest)?
+            }
+        };
+
+        Ok(Client {
+            http_client,
+            backends: Arc::new(Backends::new(backends, self.failover_cooldown)),
+            read_timeout: self.read_timeout,
+            retry: self.retry,
+            rate_limiter: self
+                .rate_limit
+                .map(|l| Arc::new(RateLimiter::new(l, max_hold))),
+            batch_concurrency: self.batch_concurrency,
+            cache: self.cache.map(|c| Arc::new(ResponseCache::new(c))),
+            disk_cache: self
+                .disk_cache
+                .map(DiskCache::open)
+                .transpose()?
+                .map(Arc::new),
+            in_flight: Arc::default(),
+            usage: Arc::new(UsageTracker::new(self.credit_alert)),
+            follows_chain: false,
+        })
+    }
+}
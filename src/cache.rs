@@ -0,0 +1,468 @@
+//! An optional in-memory LRU cache for chain data that can't change.
+//!
+//! Enable it with [`crate::ClientBuilder::cache`]. Responses are cached by
+//! request path, shared by every clone of the client, and evicted least
+//! recently used first once [`CacheConfig::max_bytes`] is exceeded. Only
+//! successful `GET`s are cached, and only for endpoints whose answer is
+//! fixed:
+//!
+//! | Endpoint | Cached when |
+//! |---|---|
+//! | `block/:hash`, `block/:hash/header`, `block/:hash/raw`, `block/:hash/txids`, `block/:hash/txid/:index` | always (a block hash commits to its contents) |
+//! | `tx/:txid`, `tx/:txid/status`, `tx/:txid/merkle-proof` | confirmed at least [`CacheConfig::min_confirmations`] deep |
+//! | `tx/:txid/hex`, `tx/:txid/raw` | the transaction's JSON or status is cached (so it is known to be deep) |
+//! | `block-height/:height` | at least `min_confirmations` deep |
+//!
+//! Everything else — mempool, address and fee queries, the tip, block status —
+//! always goes to the server.
+//!
+//! Depth is measured against the last tip height the client fetched
+//! (`blocks/tip/height`, which [`crate::HeaderChain`], [`crate::TxWatcher`]
+//! and [`crate::Client::broadcast_and_confirm`] all request); until one has
+//! been seen, nothing depth-dependent is cached. Those entries are
+//! reorg-sensitive and are dropped:
+//!
+//! - when a [`crate::HeaderChain`] sync reports a [`crate::ChainEvent::Reorg`]
+//!   or a [`crate::Client::subscribe_blocks`] stream disconnects blocks: every
+//!   entry above the fork point, however the chain's length changed;
+//! - whenever the tip height changes: any that are no longer
+//!   `min_confirmations` deep under the new tip, which happens when the chain
+//!   got shorter.
+//!
+//! A client that follows the chain with neither of those only notices reorgs
+//! that shorten it, so a deeper one that doesn't can leave entries from
+//! orphaned blocks behind; pick the depth accordingly.
+//!
+//! [`crate::HeaderChain`], [`crate::TxWatcher`] and
+//! [`crate::Client::broadcast_and_confirm`] never read depth-dependent
+//! entries — they are how reorgs get noticed, so a stale entry mustn't answer
+//! them — but their fresh responses replace (or, once no longer deep, drop)
+//! the cached ones.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::{MerkleProof, TxStatus};
+
+/// Default depth before a confirmed response counts as immutable.
+pub const DEFAULT_MIN_CONFIRMATIONS: u32 = 6;
+
+/// Cache parameters. Attach with [`crate::ClientBuilder::cache`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    /// Upper bound on cached response bodies plus their keys, in bytes. A
+    /// single response larger than this is not cached.
+    pub max_bytes: usize,
+    /// Confirmations a transaction (or height lookup) needs before it is
+    /// cached. At least 1.
+    pub min_confirmations: u32,
+}
+
+impl CacheConfig {
+    /// A cache of up to `max_bytes`, with [`DEFAULT_MIN_CONFIRMATIONS`].
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+        }
+    }
+
+    /// Sets the depth before confirmed data is cached.
+    #[must_use]
+    pub fn min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+}
+
+/// A snapshot of the cache's counters, from [`crate::Client::cache_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups of cacheable endpoints answered from the cache.
+    pub hits: u64,
+    /// Lookups of cacheable endpoints that went to the server.
+    pub misses: u64,
+    /// Entries dropped to stay under [`CacheConfig::max_bytes`].
+    pub evictions: u64,
+    /// Reorg-sensitive entries dropped after a tip change.
+    pub invalidations: u64,
+    /// Entries currently cached.
+    pub entries: usize,
+    /// Bytes currently cached (bodies plus keys).
+    pub bytes: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    body: Bytes,
+    /// The entry's position in [`Lru::order`].
+    used: u64,
+    /// For reorg-sensitive entries, the block height they depend on.
+    height: Option<u64>,
+}
+
+impl Entry {
+    fn cost(&self, key: &str) -> usize {
+        self.body.len() + key.len()
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    /// Keys by last use, oldest first.
+    order: BTreeMap<u64, String>,
+    clock: u64,
+    tip_height: Option<u64>,
+    stats: CacheStats,
+}
+
+impl Lru {
+    fn touch(&mut self, key: &str) -> Option<Bytes> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        self.order.remove(&entry.used);
+        entry.used = self.clock;
+        self.order.insert(self.clock, key.to_string());
+        Some(entry.body.clone())
+    }
+
+    fn remove(&mut self, key: &str) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.order.remove(&entry.used);
+        self.stats.bytes -= entry.cost(key);
+        self.stats.entries -= 1;
+        Some(entry)
+    }
+
+    /// Whether a block at `height` is deep enough under the known tip.
+    fn is_deep(&self, height: u64, min_confirmations: u32) -> bool {
+        is_deep(self.tip_height, height, min_confirmations)
+    }
+}
+
+/// Whether a block at `height` has `min_confirmations` under `tip_height`;
+/// `false` while the tip is unknown.
+pub(crate) fn is_deep(tip_height: Option<u64>, height: u64, min_confirmations: u32) -> bool {
+    tip_height.is_some_and(|tip| tip.saturating_sub(height) + 1 >= u64::from(min_confirmations))
+}
+
+/// The cache shared by a client and its clones.
+#[derive(Debug)]
+pub(crate) struct ResponseCache {
+    config: CacheConfig,
+    lru: Mutex<Lru>,
+}
+
+/// How a path may be cached.
+enum Kind {
+    /// Never cached.
+    Uncacheable,
+    /// `blocks/tip/height`: never cached, but updates the known tip.
+    TipHeight,
+    /// Content-addressed by block hash.
+    Immutable,
+    /// Depends on the block the data was confirmed in.
+    Confirmed,
+    /// `tx/:txid/hex` or `/raw`: cacheable once the transaction is.
+    TxBytes { txid: String },
+}
+
+impl Kind {
+    fn of(path: &str) -> Self {
+        let segments: Vec<&str> = path.split('/').collect();
+        match segments.as_slice() {
+            ["blocks", "tip", "height"] => Self::TipHeight,
+            ["block", _] | ["block", _, "header" | "raw" | "txids"] | ["block", _, "txid", _] => {
+                Self::Immutable
+            }
+            ["block-height", _] | ["tx", _] | ["tx", _, "status" | "merkle-proof"] => {
+                Self::Confirmed
+            }
+            ["tx", txid, "hex" | "raw"] => Self::TxBytes {
+                txid: (*txid).to_string(),
+            },
+            _ => Self::Uncacheable,
+        }
+    }
+}
+
+impl ResponseCache {
+    pub(crate) fn new(config: CacheConfig) -> Self {
+        Self {
+            config: CacheConfig {
+                min_confirmations: config.min_confirmations.max(1),
+                ..config
+            },
+            lru: Mutex::new(Lru::default()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Lru> {
+        self.lru.lock().expect("response cache lock poisoned")
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        self.lock().stats
+    }
+
+    /// Returns the cached body for `path`, counting a hit or miss if the
+    /// endpoint is cacheable at all. Without `sensitive`, entries that a
+    /// reorg could invalidate are never served.
+    pub(crate) fn get(&self, path: &str, sensitive: bool) -> Option<Bytes> {
+        match Kind::of(path) {
+            Kind::Uncacheable | Kind::TipHeight => return None,
+            Kind::Confirmed if !sensitive => return None,
+            _ => {}
+        }
+        let mut lru = self.lock();
+        let hit = lru.touch(path);
+        if hit.is_some() {
+            lru.stats.hits += 1;
+        } else {
+            lru.stats.misses += 1;
+        }
+        hit
+    }
+
+    /// Offers a fresh response for `path`; it is stored only if the
+    /// endpoint and the data allow it (see the [module docs](self)).
+    pub(crate) fn put(&self, path: &str, body: &Bytes) {
+        let mut lru = self.lock();
+        let height = match Kind::of(path) {
+            Kind::Uncacheable => return,
+            Kind::TipHeight => {
+                if let Some(height) = parse_height(body) {
+                    self.on_tip_height(&mut lru, height);
+                }
+                return;
+            }
+            Kind::Immutable => None,
+            Kind::Confirmed => match confirmed_height(path, body) {
+                Some(height) if lru.is_deep(height, self.config.min_confirmations) => Some(height),
+                _ => {
+                    // A fresh answer that is no longer deep replaces an old one.
+                    if lru.remove(path).is_some() {
+                        lru.stats.invalidations += 1;
+                    }
+                    return;
+                }
+            },
+            Kind::TxBytes { txid } => {
+                let known = [format!("tx/{txid}"), format!("tx/{txid}/status")]
+                    .iter()
+                    .find_map(|key| lru.entries.get(key).and_then(|e| e.height));
+                match known {
+                    Some(height) => Some(height),
+                    None => return,
+                }
+            }
+        };
+        self.insert(&mut lru, path, body.clone(), height);
+    }
+
+    fn insert(&self, lru: &mut Lru, key: &str, body: Bytes, height: Option<u64>) {
+        lru.remove(key);
+        let cost = body.len() + key.len();
+        if cost > self.config.max_bytes {
+            return;
+        }
+        while lru.stats.bytes + cost > self.config.max_bytes {
+            let Some((_, oldest)) = lru.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = lru.entries.remove(&oldest) {
+                lru.stats.bytes -= entry.cost(&oldest);
+                lru.stats.entries -= 1;
+                lru.stats.evictions += 1;
+            }
+        }
+        lru.clock += 1;
+        let used = lru.clock;
+        lru.order.insert(used, key.to_string());
+        lru.entries
+            .insert(key.to_string(), Entry { body, used, height });
+        lru.stats.bytes += cost;
+        lru.stats.entries += 1;
+    }
+
+    /// Records a new tip height, dropping reorg-sensitive entries that are no
+    /// longer deep enough under it.
+    fn on_tip_height(&self, lru: &mut Lru, height: u64) {
+        if lru.tip_height == Some(height) {
+            return;
+        }
+        lru.tip_height = Some(height);
+        let min_confirmations = self.config.min_confirmations;
+        let dropped = invalidate(lru, |h| !is_deep(Some(height), h, min_confirmations));
+        if dropped > 0 {
+            debug!(
+                target: "esplora_rs",
+                "tip moved to {}; dropped {} cached entries", height, dropped
+            );
+        }
+    }
+
+    /// Drops reorg-sensitive entries for blocks above `fork_height`, after a
+    /// reorg back to it was detected.
+    pub(crate) fn on_reorg(&self, fork_height: u64) {
+        let mut lru = self.lock();
+        let dropped = invalidate(&mut lru, |h| h > fork_height);
+        if dropped > 0 {
+            debug!(
+                target: "esplora_rs",
+                "reorg back to height {}; dropped {} cached entries", fork_height, dropped
+            );
+        }
+    }
+}
+
+/// Removes the reorg-sensitive entries whose height is `stale`, returning
+/// how many there were.
+fn invalidate(lru: &mut Lru, stale: impl Fn(u64) -> bool) -> usize {
+    let keys: Vec<String> = lru
+        .entries
+        .iter()
+        .filter(|(_, e)| e.height.is_some_and(&stale))
+        .map(|(key, _)| key.clone())
+        .collect();
+    for key in &keys {
+        lru.remove(key);
+        lru.stats.invalidations += 1;
+    }
+    keys.len()
+}
+
+pub(crate) fn parse_height(body: &[u8]) -> Option<u64> {
+    std::str::from_utf8(body).ok()?.trim().parse().ok()
+}
+
+/// The confirming height of a [`Kind::Confirmed`] response, or `None` if it
+/// is unconfirmed (or unparseable).
+pub(crate) fn confirmed_height(path: &str, body: &[u8]) -> Option<u64> {
+    #[derive(Deserialize)]
+    struct WithStatus {
+        status: TxStatus,
+    }
+
+    if let Some(height) = path.strip_prefix("block-height/") {
+        return height.parse().ok();
+    }
+    if path.ends_with("/merkle-proof") {
+        let proof: MerkleProof = serde_json::from_slice(body).ok()?;
+        return Some(proof.block_height);
+    }
+    let status = if path.ends_with("/status") {
+        serde_json::from_slice::<TxStatus>(body).ok()?
+    } else {
+        serde_json::from_slice::<WithStatus>(body).ok()?.status
+    };
+    status.block_height.filter(|_| status.confirmed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(max_bytes: usize) -> ResponseCache {
+        ResponseCache::new(CacheConfig::new(max_bytes).min_confirmations(2))
+    }
+
+    #[test]
+    fn evicts_least_recently_used_by_bytes() {
+        let cache = cache(3 * (100 + "block/a".len()));
+        for key in ["block/a", "block/b", "block/c"] {
+            cache.put(key, &Bytes::from(vec![0u8; 100]));
+        }
+        assert!(cache.get("block/a", true).is_some()); // a is now the newest
+        cache.put("block/d", &Bytes::from(vec![0u8; 100]));
+        assert!(cache.get("block/b", true).is_none());
+        assert!(cache.get("block/a", true).is_some());
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.evictions), (3, 1));
+        assert_eq!(stats.bytes, 3 * 107);
+        assert_eq!((stats.hits, stats.misses), (2, 1));
+
+        // Too big for the whole cache: not stored, nothing evicted.
+        cache.put("block/e/raw", &Bytes::from(vec![0u8; 1000]));
+        assert_eq!(cache.stats().entries, 3);
+    }
+
+    #[test]
+    fn never_caches_mutable_endpoints() {
+        let cache = cache(1 << 20);
+        for path in [
+            "blocks/tip/hash",
+            "mempool",
+            "address/x/txs",
+            "block/h/status",
+        ] {
+            cache.put(path, &Bytes::from_static(b"x"));
+            assert!(cache.get(path, true).is_none());
+        }
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn caches_confirmed_data_once_deep_and_drops_it_on_reorg() {
+        let cache = cache(1 << 20);
+        let status = Bytes::from_static(br#"{"confirmed":true,"block_height":100}"#);
+        cache.put("tx/t/status", &status);
+        assert!(cache.get("tx/t/status", true).is_none(), "no tip known yet");
+
+        cache.put("blocks/tip/height", &Bytes::from_static(b"100"));
+        cache.put("tx/t/status", &status);
+        assert!(
+            cache.get("tx/t/status", true).is_none(),
+            "only 1 confirmation"
+        );
+        cache.put("tx/t/hex", &Bytes::from_static(b"00"));
+        assert!(cache.get("tx/t/hex", true).is_none(), "tx not known deep");
+
+        cache.put("blocks/tip/height", &Bytes::from_static(b"101"));
+        cache.put("tx/t/status", &status);
+        cache.put("tx/t/hex", &Bytes::from_static(b"00"));
+        cache.put("block-height/101", &Bytes::from_static(b"h"));
+        assert_eq!(cache.get("tx/t/status", true), Some(status));
+        assert!(cache.get("tx/t/hex", true).is_some());
+        assert!(cache.get("block-height/101", true).is_none());
+
+        let unconfirmed = Bytes::from_static(br#"{"confirmed":false}"#);
+        cache.put("tx/u/status", &unconfirmed);
+        assert!(cache.get("tx/u/status", true).is_none());
+
+        // The chain shrinks to 100: height 100 is no longer 2 deep.
+        cache.put("block/b", &Bytes::from_static(b"{}"));
+        cache.put("blocks/tip/height", &Bytes::from_static(b"100"));
+        assert!(cache.get("tx/t/status", true).is_none());
+        assert!(cache.get("tx/t/hex", true).is_none());
+        assert!(cache.get("block/b", true).is_some());
+        assert_eq!(cache.stats().invalidations, 2);
+    }
+
+    #[test]
+    fn drops_entries_above_the_fork_point_on_reorg() {
+        let cache = cache(1 << 20);
+        cache.put("blocks/tip/height", &Bytes::from_static(b"110"));
+        for (txid, height) in [("a", 100), ("b", 105), ("c", 106)] {
+            let status = format!(r#"{{"confirmed":true,"block_height":{height}}}"#);
+            cache.put(&format!("tx/{txid}/status"), &Bytes::from(status));
+        }
+        cache.put("block/h", &Bytes::from_static(b"{}"));
+        assert_eq!(cache.stats().entries, 4);
+
+        // A same-height reorg back to 105: the tip height alone can't tell.
+        cache.put("blocks/tip/height", &Bytes::from_static(b"110"));
+        assert_eq!(cache.stats().invalidations, 0);
+        cache.on_reorg(105);
+        assert!(cache.get("tx/a/status", true).is_some());
+        assert!(cache.get("tx/b/status", true).is_some());
+        assert!(cache.get("tx/c/status", true).is_none());
+        assert!(cache.get("block/h", true).is_some());
+        assert_eq!(cache.stats().invalidations, 1);
+    }
+}
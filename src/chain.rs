@@ -0,0 +1,360 @@
+//! [`HeaderChain`]: a locally validated view of the server's best chain.
+//!
+//! Each [`HeaderChain::sync`] brings the stored headers up to the server's
+//! tip. Every new block must hash to its id, meet the proof of work its own
+//! `bits` claim, and link to the previous stored header via
+//! `previousblockhash`. When the server's chain no longer contains our tip,
+//! the chain walks back to the last height where both agree, rewinds the
+//! store to it and reports a [`ChainEvent::Reorg`] naming that fork point and
+//! the blocks that were disconnected — which is what a wallet needs to roll
+//! back confirmations.
+//!
+//! Headers live in a pluggable [`HeaderStore`]; [`MemoryStore`] is the
+//! in-memory default. The chain starts from a caller-chosen height (a
+//! checkpoint the caller trusts) rather than genesis, and a reorg deeper than
+//! the stored headers is reported as [`Error::InvalidChain`].
+//!
+//! `bits` is checked against the block's own hash, not against the
+//! difficulty the network requires at that height, so the trust placed in
+//! the starting checkpoint still matters.
+
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use tracing::{debug, warn};
+
+use crate::paginate::BLOCKS_PAGE_SIZE;
+use crate::{Block, BlockHeader, Client, Error};
+
+/// How many times [`HeaderChain::sync`] starts over when the server's chain
+/// changes underneath it mid-sync.
+const MAX_SYNC_ROUNDS: usize = 3;
+
+/// A validated header at a known height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HeaderEntry {
+    /// The block height.
+    pub height: u64,
+    /// The block hash (display hex).
+    pub hash: String,
+    /// The decoded header.
+    pub header: BlockHeader,
+}
+
+impl HeaderEntry {
+    /// An entry for `header` at `height`; the hash is computed from it.
+    pub fn new(height: u64, header: BlockHeader) -> Self {
+        Self {
+            height,
+            hash: header.block_hash(),
+            header,
+        }
+    }
+}
+
+/// What changed during a [`HeaderChain::sync`], in the order it happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    /// A block was appended to the chain.
+    Connected(HeaderEntry),
+    /// The server's chain forked away from ours. Everything above
+    /// `fork_point` was removed; the replacement blocks follow as
+    /// [`ChainEvent::Connected`] events.
+    Reorg {
+        /// The highest block both chains share, which is now our tip.
+        fork_point: HeaderEntry,
+        /// The removed blocks, highest first.
+        disconnected: Vec<HeaderEntry>,
+    },
+}
+
+/// Where a [`HeaderChain`] keeps its headers. Implement this to persist them
+/// (a file, a database table); heights in a store are always contiguous.
+#[async_trait]
+pub trait HeaderStore: Send + Sync {
+    /// The highest stored entry, or `None` if the store is empty.
+    async fn tip(&self) -> Result<Option<HeaderEntry>, Error>;
+
+    /// The entry at `height`, or `None` if it isn't stored.
+    async fn get(&self, height: u64) -> Result<Option<HeaderEntry>, Error>;
+
+    /// Appends `entry`, whose height is one above the current tip (or
+    /// anything, if the store is empty).
+    async fn push(&mut self, entry: HeaderEntry) -> Result<(), Error>;
+
+    /// Removes every entry above `height` and returns them, highest first.
+    async fn rewind(&mut self, height: u64) -> Result<Vec<HeaderEntry>, Error>;
+}
+
+/// A [`HeaderStore`] that keeps everything in memory.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    entries: BTreeMap<u64, HeaderEntry>,
+}
+
+impl MemoryStore {
+    /// An empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of stored headers.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the store is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[async_trait]
+impl HeaderStore for MemoryStore {
+    async fn tip(&self) -> Result<Option<HeaderEntry>, Error> {
+        Ok(self.entries.values().next_back().cloned())
+    }
+
+    async fn get(&self, height: u64) -> Result<Option<HeaderEntry>, Error> {
+        Ok(self.entries.get(&height).cloned())
+    }
+
+    async fn push(&mut self, entry: HeaderEntry) -> Result<(), Error> {
+        self.entries.insert(entry.height, entry);
+        Ok(())
+    }
+
+    async fn rewind(&mut self, height: u64) -> Result<Vec<HeaderEntry>, Error> {
+        let removed = self.entries.split_off(&(height + 1));
+        Ok(removed.into_values().rev().collect())
+    }
+}
+
+/// Tracks the server's best chain, validating every header it stores. See
+/// the [module docs](self).
+///
+/// ```no_run
+/// # async fn demo(client: esplora_rs::Client) -> Result<(), esplora_rs::Error> {
+/// use esplora_rs::{ChainEvent, HeaderChain, MemoryStore};
+///
+/// let mut chain = HeaderChain::new(client, MemoryStore::new(), 850_000);
+/// for event in chain.sync().await? {
+///     match event {
+///         ChainEvent::Connected(entry) => println!("+ {} {}", entry.height, entry.hash),
+///         ChainEvent::Reorg { fork_point, disconnected } => {
+///             println!("reorg at {}: {} blocks undone", fork_point.height, disconnected.len())
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct HeaderChain<S = MemoryStore> {
+    client: Client,
+    store: S,
+    start_height: u64,
+    /// Events from a sync that failed validation, delivered by the next one.
+    pending: Vec<ChainEvent>,
+}
+
+impl<S: HeaderStore> HeaderChain<S> {
+    /// A chain backed by `store`. If the store is empty, the first sync
+    /// starts at `start_height`; otherwise it continues from the stored tip.
+    pub fn new(client: Client, store: S, start_height: u64) -> Self {
+        Self {
+            client: client.following_chain(),
+            store,
+            start_height,
+            pending: Vec::new(),
+        }
+    }
+
+    /// The underlying store.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    /// Consumes the chain, returning its store.
+    pub fn into_store(self) -> S {
+        self.store
+    }
+
+    /// The highest validated header, if any.
+    ///
+    /// # Errors
+    /// Returns the store's error, if it fails.
+    pub async fn tip(&self) -> Result<Option<HeaderEntry>, Error> {
+        self.store.tip().await
+    }
+
+    /// Brings the chain up to the server's tip, returning what changed.
+    ///
+    /// If a request or the store fails after the store has already changed,
+    /// the events so far are returned (and the failure logged) rather than
+    /// lost; the next sync resumes from the stored tip and reports the
+    /// failure if it persists. A block that fails validation is always
+    /// reported as an error; the events applied before it are then returned
+    /// by the next successful sync.
+    ///
+    /// # Errors
+    /// Returns [`Error::InvalidChain`] if a block fails validation, the
+    /// server's chain forks below the earliest stored header, or it keeps
+    /// changing mid-sync; otherwise any request or store error.
+    pub async fn sync(&mut self) -> Result<Vec<ChainEvent>, Error> {
+        let mut events = std::mem::take(&mut self.pending);
+        match self.sync_into(&mut events).await {
+            Ok(()) => Ok(events),
+            Err(err @ Error::InvalidChain(_)) => {
+                self.pending = events;
+                Err(err)
+            }
+            Err(err) if !events.is_empty() => {
+                warn!(
+                    target: "esplora_rs",
+                    "header sync stopped after {} event(s): {}", events.len(), err
+                );
+                Ok(events)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn sync_into(&mut self, events: &mut Vec<ChainEvent>) -> Result<(), Error> {
+        for _ in 0..MAX_SYNC_ROUNDS {
+            let tip_height = self.client.get_tip_height().await?;
+            let local = match self.store.tip().await? {
+                Some(local) => local,
+                None => {
+                    let entry = self.fetch_entry(self.start_height).await?;
+                    self.store.push(entry.clone()).await?;
+                    events.push(ChainEvent::Connected(entry.clone()));
+                    entry
+                }
+            };
+            if let Some(fork_point) = self.find_fork(&local, tip_height).await? {
+                let disconnected = self.store.rewind(fork_point.height).await?;
+                warn!(
+                    target: "esplora_rs",
+                    "reorg: {} block(s) disconnected back to {} at height {}",
+                    disconnected.len(), fork_point.hash, fork_point.height
+                );
+                self.client.note_reorg(fork_point.height).await;
+                events.push(ChainEvent::Reorg {
+                    fork_point,
+                    disconnected,
+                });
+            }
+            if self.connect_up_to(tip_height, events).await? {
+                return Ok(());
+            }
+            debug!(target: "esplora_rs", "chain changed during sync; starting over");
+        }
+        Err(Error::InvalidChain(format!(
+            "server chain kept changing during sync ({MAX_SYNC_ROUNDS} attempts)"
+        )))
+    }
+
+    /// Returns the highest stored entry the server still agrees with, if the
+    /// server disagrees at a stored height (i.e. a reorg happened); `None` if
+    /// `local` is still on the server's chain, or the server merely lags
+    /// behind it but agrees up to its own tip.
+    async fn find_fork(
+        &self,
+        local: &HeaderEntry,
+        tip_height: u64,
+    ) -> Result<Option<HeaderEntry>, Error> {
+        let start = local.height.min(tip_height);
+        let mut height = start;
+        loop {
+            let ours = self.store.get(height).await?.ok_or_else(|| {
+                Error::InvalidChain(format!(
+                    "reorg below the earliest stored header (height {height})"
+                ))
+            })?;
+            let theirs = self.client.get_block_hash_from_height(height).await?;
+            if theirs.eq_ignore_ascii_case(&ours.hash) {
+                if height == start && start < local.height {
+                    debug!(
+                        target: "esplora_rs",
+                        "server tip {} is behind ours at {}; waiting", tip_height, local.height
+                    );
+                }
+                return Ok((height < start).then_some(ours));
+            }
+            height = height.checked_sub(1).ok_or_else(|| {
+                Error::InvalidChain("server disagrees on the genesis block".to_string())
+            })?;
+        }
+    }
+
+    /// Appends validated blocks up to `tip_height`, a page at a time.
+    /// Returns `false` if a block no longer links to our tip, meaning the
+    /// server reorganized after [`Self::find_fork`] looked.
+    async fn connect_up_to(
+        &mut self,
+        tip_height: u64,
+        events: &mut Vec<ChainEvent>,
+    ) -> Result<bool, Error> {
+        let mut prev = self
+            .store
+            .tip()
+            .await?
+            .expect("store has a tip after sync start");
+        while prev.height < tip_height {
+            let top = (prev.height + BLOCKS_PAGE_SIZE).min(tip_height);
+            let mut blocks = self.client.get_blocks(Some(top)).await?;
+            blocks.retain(|b| b.height > prev.height);
+            blocks.sort_by_key(|b| b.height);
+            if blocks.is_empty() {
+                return Err(Error::InvalidChain(format!(
+                    "server listed no blocks above height {}",
+                    prev.height
+                )));
+            }
+            for block in blocks {
+                if block.height != prev.height + 1 {
+                    return Err(Error::InvalidChain(format!(
+                        "server skipped from height {} to {}",
+                        prev.height, block.height
+                    )));
+                }
+                let links = block
+                    .previousblockhash
+                    .as_deref()
+                    .is_some_and(|p| p.eq_ignore_ascii_case(&prev.hash));
+                if !links {
+                    return Ok(false);
+                }
+                let entry = validate(&block)?;
+                self.store.push(entry.clone()).await?;
+                events.push(ChainEvent::Connected(entry.clone()));
+                prev = entry;
+            }
+        }
+        Ok(true)
+    }
+
+    async fn fetch_entry(&self, height: u64) -> Result<HeaderEntry, Error> {
+        let hash = self.client.get_block_hash_from_height(height).await?;
+        validate(&self.client.get_block(&hash).await?)
+    }
+}
+
+/// Checks that `block` hashes to its id and meets its proof of work.
+fn validate(block: &Block) -> Result<HeaderEntry, Error> {
+    let entry = HeaderEntry::new(block.height, BlockHeader::from_block(block)?);
+    if !entry.hash.eq_ignore_ascii_case(&block.id) {
+        return Err(Error::InvalidChain(format!(
+            "block at height {} hashes to {}, not its id {}",
+            block.height, entry.hash, block.id
+        )));
+    }
+    if !entry.header.check_pow() {
+        return Err(Error::InvalidChain(format!(
+            "block {} at height {} fails proof of work",
+            block.id, block.height
+        )));
+    }
+    Ok(entry)
+}
@@ -0,0 +1,528 @@
+//! An optional on-disk cache of blocks and confirmed transactions, for warm
+//! starts and offline use.
+//!
+//! Enable it with [`crate::ClientBuilder::disk_cache`]. Three endpoints are
+//! persisted, one file per response:
+//!
+//! | Endpoint | Stored when |
+//! |---|---|
+//! | `block/:hash` ([`crate::Block`]) | always |
+//! | `tx/:txid` ([`crate::Transaction`]) | confirmed at least [`DiskCacheConfig::min_confirmations`] deep under the last tip height the client fetched |
+//! | `tx/:txid/raw` | its `tx/:txid` is already stored |
+//!
+//! Files live under `<dir>/entries/`, named by the SHA-256 of the request
+//! path (plus `-<height>` for a transaction, the height it confirmed at),
+//! next to a `VERSION` stamp. When the stamp doesn't match
+//! [`DISK_CACHE_VERSION`] (a different format), every entry is discarded.
+//! Once [`DiskCacheConfig::max_bytes`] is exceeded, the oldest files are
+//! deleted first.
+//!
+//! Stored transactions are dropped when a reorg above their height is
+//! detected — by a [`crate::HeaderChain`] sync or a
+//! [`crate::Client::subscribe_blocks`] stream, as for the
+//! [memory cache](crate::cache) — or when a fresh response no longer shows
+//! them deeply confirmed. Blocks and raw transactions are content-addressed
+//! and stay.
+//!
+//! In [offline mode](DiskCacheConfig::offline) the client sends no requests:
+//! whatever the memory and disk caches hold is served, and anything else
+//! fails with [`crate::Error::OfflineMiss`].
+//!
+//! The cache is best effort: a file that can't be read or written is logged
+//! and treated as a miss. Reads, writes and deletions run on tokio's
+//! blocking pool, outside the lock on the cache's index, so a slow disk
+//! holds up only the requests that touch it.
+
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use tracing::{debug, warn};
+
+use crate::cache::{confirmed_height, is_deep, parse_height, DEFAULT_MIN_CONFIRMATIONS};
+use crate::hashes::{encode_hex, sha256};
+use crate::Error;
+
+/// The on-disk format version. Bumped whenever a cached response's format
+/// changes; a cache written under another version is discarded on open.
+pub const DISK_CACHE_VERSION: u32 = 2;
+
+const VERSION_FILE: &str = "VERSION";
+const ENTRIES_DIR: &str = "entries";
+
+/// Disk cache parameters. Attach with [`crate::ClientBuilder::disk_cache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCacheConfig {
+    /// The cache directory; created if missing.
+    pub dir: PathBuf,
+    /// Upper bound on the stored response bodies, in bytes.
+    pub max_bytes: u64,
+    /// Confirmations a transaction needs before it is stored. At least 1.
+    pub min_confirmations: u32,
+    /// Serve only cached data and send no requests.
+    pub offline: bool,
+}
+
+impl DiskCacheConfig {
+    /// A cache in `dir` of up to `max_bytes`, with
+    /// [`DEFAULT_MIN_CONFIRMATIONS`], online.
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_bytes,
+            min_confirmations: DEFAULT_MIN_CONFIRMATIONS,
+            offline: false,
+        }
+    }
+
+    /// Sets the depth before a transaction is stored.
+    #[must_use]
+    pub fn min_confirmations(mut self, min_confirmations: u32) -> Self {
+        self.min_confirmations = min_confirmations;
+        self
+    }
+
+    /// Enables or disables offline mode.
+    #[must_use]
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    /// Entry keys (the hashed request paths), oldest first.
+    order: VecDeque<String>,
+    stored: HashMap<String, Stored>,
+    bytes: u64,
+    tip_height: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stored {
+    size: u64,
+    /// For a transaction, the height it confirmed at.
+    height: Option<u64>,
+}
+
+/// An open disk cache, shared by a client and its clones.
+#[derive(Debug)]
+pub(crate) struct DiskCache {
+    config: DiskCacheConfig,
+    entries: PathBuf,
+    index: Mutex<Index>,
+}
+
+impl DiskCache {
+    /// Opens (or creates) the cache directory, discarding it if its version
+    /// stamp doesn't match.
+    pub(crate) fn open(config: DiskCacheConfig) -> Result<Self, Error> {
+        let config = DiskCacheConfig {
+            min_confirmations: config.min_confirmations.max(1),
+            ..config
+        };
+        let entries = config.dir.join(ENTRIES_DIR);
+        let index = load(&config.dir, &entries)
+            .map_err(|e| Error::Config(format!("disk cache at {}: {e}", config.dir.display())))?;
+        let cache = Self {
+            config,
+            entries,
+            index: Mutex::new(index),
+        };
+        let evicted = cache.evict(&mut cache.lock());
+        remove_files(&cache.entries, evicted);
+        Ok(cache)
+    }
+
+    pub(crate) fn offline(&self) -> bool {
+        self.config.offline
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index.lock().expect("disk cache lock poisoned")
+    }
+
+    /// Returns the stored body for `path`, if any. Without `sensitive`,
+    /// transactions (whose confirmation a reorg could undo) are never served.
+    pub(crate) async fn get(&self, path: &str, sensitive: bool) -> Option<Bytes> {
+        if !persisted(path) || (!sensitive && reorg_sensitive(path)) {
+            return None;
+        }
+        let key = entry_key(path);
+        let height = self.lock().stored.get(&key)?.height;
+        let file = self.entries.join(file_name(&key, height));
+        match blocking(move || fs::read(file)).await {
+            Ok(body) => Some(Bytes::from(body)),
+            Err(e) => {
+                warn!(target: "esplora_rs", "disk cache read of {} failed: {}", path, e);
+                forget(&mut self.lock(), &key);
+                None
+            }
+        }
+    }
+
+    /// Offers a fresh response for `path`; it is stored only if it is one of
+    /// the persisted endpoints and the data allows it.
+    pub(crate) async fn put(&self, path: &str, body: &Bytes) {
+        let key = entry_key(path);
+        let (storable, height) = {
+            let mut index = self.lock();
+            if path == "blocks/tip/height" {
+                index.tip_height = parse_height(body).or(index.tip_height);
+                return;
+            }
+            match path.split('/').collect::<Vec<_>>().as_slice() {
+                ["block", _] => (true, None),
+                ["tx", _] => {
+                    let height = confirmed_height(path, body);
+                    let deep = height.is_some_and(|h| {
+                        is_deep(index.tip_height, h, self.config.min_confirmations)
+                    });
+                    (deep, height)
+                }
+                ["tx", txid, "raw"] => {
+                    let tx = entry_key(&format!("tx/{txid}"));
+                    (index.stored.contains_key(&tx), None)
+                }
+                _ => return,
+            }
+        };
+        if !storable {
+            // A fresh answer that is no longer deep replaces an old one.
+            let stale = forget(&mut self.lock(), &key);
+            self.remove(stale.into_iter().collect()).await;
+            return;
+        }
+        if self.lock().stored.contains_key(&key) || body.len() as u64 > self.config.max_bytes {
+            return;
+        }
+
+        let name = file_name(&key, height);
+        let (dir, data) = (self.entries.clone(), body.clone());
+        let written = blocking(move || write_atomic(&dir, &name, &data)).await;
+
+        let evicted = {
+            let mut index = self.lock();
+            if let Err(e) = written {
+                warn!(target: "esplora_rs", "disk cache write of {} failed: {}", path, e);
+                return;
+            }
+            if index.stored.contains_key(&key) {
+                return; // a concurrent put stored it first
+            }
+            let size = body.len() as u64;
+            index.order.push_back(key.clone());
+            index.stored.insert(key, Stored { size, height });
+            index.bytes += size;
+            self.evict(&mut index)
+        };
+        self.remove(evicted).await;
+    }
+
+    /// Drops the transactions confirmed above `fork_height`, after a reorg
+    /// back to it was detected.
+    pub(crate) async fn on_reorg(&self, fork_height: u64) {
+        let orphaned = {
+            let mut index = self.lock();
+            let keys: Vec<String> = (index.stored.iter())
+                .filter(|(_, s)| s.height.is_some_and(|h| h > fork_height))
+                .map(|(key, _)| key.clone())
+                .collect();
+            keys.iter()
+                .filter_map(|key| forget(&mut index, key))
+                .collect::<Vec<_>>()
+        };
+        if !orphaned.is_empty() {
+            debug!(
+                target: "esplora_rs",
+                "reorg back to height {}; dropping {} disk cache entries", fork_height, orphaned.len()
+            );
+        }
+        self.remove(orphaned).await;
+    }
+
+    /// Drops the oldest entries from the index until the cache fits its cap,
+    /// returning the files to delete.
+    fn evict(&self, index: &mut Index) -> Vec<String> {
+        let mut evicted = Vec::new();
+        while index.bytes > self.config.max_bytes {
+            let Some(key) = index.order.front().cloned() else {
+                break;
+            };
+            evicted.extend(forget(index, &key));
+        }
+        evicted
+    }
+
+    /// Deletes `files` on the blocking pool.
+    async fn remove(&self, files: Vec<String>) {
+        if files.is_empty() {
+            return;
+        }
+        let dir = self.entries.clone();
+        // Deletion failures are logged by `remove_files`.
+        let _ = blocking(move || {
+            remove_files(&dir, files);
+            Ok(())
+        })
+        .await;
+    }
+}
+
+/// Runs blocking file I/O on tokio's blocking pool.
+async fn blocking<T: Send + 'static>(
+    op: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> io::Result<T> {
+    tokio::task::spawn_blocking(op)
+        .await
+        .unwrap_or_else(|e| Err(io::Error::other(e)))
+}
+
+fn remove_files(dir: &Path, names: Vec<String>) {
+    for name in names {
+        if let Err(e) = fs::remove_file(dir.join(&name)) {
+            if e.kind() != io::ErrorKind::NotFound {
+                warn!(target: "esplora_rs", "disk cache eviction of {} failed: {}", name, e);
+            }
+        }
+    }
+}
+
+/// Whether `path` is one of the endpoints the disk cache keeps.
+fn persisted(path: &str) -> bool {
+    matches!(
+        path.split('/').collect::<Vec<_>>().as_slice(),
+        ["block", _] | ["tx", _] | ["tx", _, "raw"]
+    )
+}
+
+/// Whether `path`'s stored answer depends on the block it was confirmed in.
+fn reorg_sensitive(path: &str) -> bool {
+    matches!(path.split('/').collect::<Vec<_>>().as_slice(), ["tx", _])
+}
+
+fn entry_key(path: &str) -> String {
+    encode_hex(&sha256(path.as_bytes()))
+}
+
+/// The file an entry is stored in: its key, plus the confirming height of a
+/// transaction.
+fn file_name(key: &str, height: Option<u64>) -> String {
+    match height {
+        Some(height) => format!("{key}-{height}"),
+        None => key.to_string(),
+    }
+}
+
+/// Removes `key` from the index, returning its file to delete.
+fn forget(index: &mut Index, key: &str) -> Option<String> {
+    let stored = index.stored.remove(key)?;
+    index.bytes -= stored.size;
+    index.order.retain(|k| k != key);
+    Some(file_name(key, stored.height))
+}
+
+/// Checks the version stamp (wiping the entries on a mismatch) and indexes
+/// the existing entries, oldest first.
+fn load(dir: &Path, entries: &Path) -> io::Result<Index> {
+    let stamp = DISK_CACHE_VERSION.to_string();
+    let version = fs::read_to_string(dir.join(VERSION_FILE)).ok();
+    if version.as_deref().map(str::trim) != Some(stamp.as_str()) {
+        if entries.exists() {
+            debug!(
+                target: "esplora_rs",
+                "disk cache version {:?} != {}; discarding entries", version, stamp
+            );
+            fs::remove_dir_all(entries)?;
+        }
+        fs::create_dir_all(entries)?;
+        fs::write(dir.join(VERSION_FILE), &stamp)?;
+    }
+    fs::create_dir_all(entries)?;
+
+    let mut found = Vec::new();
+    for entry in fs::read_dir(entries)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let meta = entry.metadata()?;
+        if name.starts_with('.') || !meta.is_file() {
+            continue; // leftovers of an interrupted write
+        }
+        found.push((meta.modified()?, name, meta.len()));
+    }
+    found.sort();
+
+    let mut index = Index::default();
+    for (_, name, size) in found {
+        let (key, height) = match name.split_once('-') {
+            Some((key, height)) => match height.parse() {
+                Ok(height) => (key.to_string(), Some(height)),
+                Err(_) => continue,
+            },
+            None => (name, None),
+        };
+        index.order.push_back(key.clone());
+        index.stored.insert(key, Stored { size, height });
+        index.bytes += size;
+    }
+    Ok(index)
+}
+
+/// Writes via a temporary file and a rename, so a crash never leaves a
+/// truncated entry under its real name. Each write gets its own temporary
+/// file, so concurrent writes of the same entry don't collide.
+fn write_atomic(dir: &Path, name: &str, body: &[u8]) -> io::Result<()> {
+    static WRITES: AtomicU64 = AtomicU64::new(0);
+    let n = WRITES.fetch_add(1, Ordering::Relaxed);
+    let tmp = dir.join(format!(".{name}.{n}.tmp"));
+    fs::write(&tmp, body)?;
+    fs::rename(&tmp, dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "esplora-rs-disk-cache-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn discards_entries_from_another_version() {
+        let dir = temp_dir("version");
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 1 << 20)).unwrap();
+        cache.put("block/aa", &Bytes::from_static(b"{}")).await;
+        assert!(cache.get("block/aa", true).await.is_some());
+        drop(cache);
+
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 1 << 20)).unwrap();
+        assert!(
+            cache.get("block/aa", true).await.is_some(),
+            "same version survives"
+        );
+        drop(cache);
+
+        fs::write(dir.join(VERSION_FILE), "0").unwrap();
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 1 << 20)).unwrap();
+        assert!(cache.get("block/aa", true).await.is_none());
+        assert_eq!(
+            fs::read_to_string(dir.join(VERSION_FILE)).unwrap(),
+            DISK_CACHE_VERSION.to_string()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn evicts_oldest_over_the_cap() {
+        let dir = temp_dir("cap");
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 250)).unwrap();
+        for hash in ["a", "b", "c"] {
+            cache
+                .put(&format!("block/{hash}"), &Bytes::from(vec![b'x'; 100]))
+                .await;
+        }
+        assert!(cache.get("block/a", true).await.is_none());
+        assert!(
+            cache.get("block/b", true).await.is_some()
+                && cache.get("block/c", true).await.is_some()
+        );
+        assert_eq!(fs::read_dir(dir.join(ENTRIES_DIR)).unwrap().count(), 2);
+        drop(cache);
+
+        // A smaller cap on reopen trims the cache right away.
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 150)).unwrap();
+        assert_eq!(cache.lock().order.len(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn drops_transactions_above_a_reorg_even_after_reopening() {
+        let dir = temp_dir("reorg");
+        let config = DiskCacheConfig::new(&dir, 1 << 20).min_confirmations(2);
+        let cache = DiskCache::open(config.clone()).unwrap();
+        let at = |height: u64| {
+            Bytes::from(format!(
+                r#"{{"status":{{"confirmed":true,"block_height":{height}}}}}"#
+            ))
+        };
+        cache
+            .put("blocks/tip/height", &Bytes::from_static(b"110"))
+            .await;
+        cache.put("tx/a", &at(100)).await;
+        cache.put("tx/b", &at(107)).await;
+        cache.put("tx/b/raw", &Bytes::from_static(b"\x01")).await;
+        cache.put("block/h", &Bytes::from_static(b"{}")).await;
+        drop(cache);
+
+        // Heights come back from the file names.
+        let cache = DiskCache::open(config).unwrap();
+        cache.on_reorg(105).await;
+        assert!(cache.get("tx/a", true).await.is_some());
+        assert!(cache.get("tx/b", true).await.is_none());
+        assert!(cache.get("tx/b/raw", true).await.is_some());
+        assert!(cache.get("block/h", true).await.is_some());
+        assert_eq!(fs::read_dir(dir.join(ENTRIES_DIR)).unwrap().count(), 3);
+
+        // A fresh answer showing the transaction unconfirmed replaces it.
+        let unconfirmed = Bytes::from_static(br#"{"status":{"confirmed":false}}"#);
+        cache.put("tx/a", &unconfirmed).await;
+        assert!(cache.get("tx/a", true).await.is_none());
+        assert_eq!(fs::read_dir(dir.join(ENTRIES_DIR)).unwrap().count(), 2);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn concurrent_puts_store_an_entry_once() {
+        let dir = temp_dir("concurrent");
+        let cache = DiskCache::open(DiskCacheConfig::new(&dir, 1 << 20)).unwrap();
+        let body = Bytes::from(vec![b'x'; 100]);
+        tokio::join!(
+            cache.put("block/a", &body),
+            cache.put("block/a", &body),
+            cache.put("block/a", &body),
+        );
+        assert_eq!(cache.get("block/a", true).await, Some(body));
+        let index = cache.lock();
+        assert_eq!((index.order.len(), index.bytes), (1, 100));
+        drop(index);
+        assert_eq!(fs::read_dir(dir.join(ENTRIES_DIR)).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stores_only_deep_transactions() {
+        let dir = temp_dir("deep");
+        let config = DiskCacheConfig::new(&dir, 1 << 20).min_confirmations(3);
+        let cache = DiskCache::open(config).unwrap();
+        let tx = Bytes::from_static(br#"{"status":{"confirmed":true,"block_height":100}}"#);
+        cache.put("tx/t", &tx).await;
+        cache
+            .put("blocks/tip/height", &Bytes::from_static(b"101"))
+            .await;
+        cache.put("tx/t", &tx).await;
+        cache.put("tx/t/raw", &Bytes::from_static(b"\x01")).await;
+        assert!(
+            cache.get("tx/t", true).await.is_none() && cache.get("tx/t/raw", true).await.is_none()
+        );
+
+        cache
+            .put("blocks/tip/height", &Bytes::from_static(b"102"))
+            .await;
+        cache.put("tx/t", &tx).await;
+        cache.put("tx/t/raw", &Bytes::from_static(b"\x01")).await;
+        assert_eq!(cache.get("tx/t", true).await, Some(tx));
+        assert!(cache.get("tx/t/raw", true).await.is_some());
+        assert!(cache.get("mempool", true).await.is_none());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,12 @@
 use thiserror::Error;
 
 /// Represents the possible errors that can occur when using the Esplora client.
+///
+/// Retries and request coalescing wrap the error that actually occurred in
+/// [`Error::RetriesExhausted`] or [`Error::Shared`]; match on
+/// [`Error::root`] to see it whichever way the call went.
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum Error {
     /// An error from the underlying `reqwest` HTTP client.
     #[error("Request error: {0}")]
@@ -12,9 +17,21 @@ pub enum Error {
     /// An error deserializing JSON data.
     #[error("JSON parsing error: {0}")]
     SerdeJson(#[from] serde_json::Error),
-    /// An error related to authentication.
-    #[error("Authentication error: {0}")]
-    Auth(String),
+    /// Getting credentials failed. For a token endpoint's OAuth error
+    /// response (RFC 6749 §5.2), `error` is its code (e.g. `invalid_client`,
+    /// `invalid_scope`) and `error_description` its explanation; for a
+    /// non-OAuth failure (discovery, a bad response, an unusable credential),
+    /// `error` says what went wrong.
+    #[error(
+        "Authentication error: {error}{}",
+        error_description.as_deref().map(|d| format!(" ({d})")).unwrap_or_default()
+    )]
+    Auth {
+        /// The OAuth error code, or a short description.
+        error: String,
+        /// The server's human-readable explanation, if any.
+        error_description: Option<String>,
+    },
     /// A required environment variable is missing.
     #[error("Missing environment variable: {0}")]
     EnvVar(String),
@@ -47,9 +64,255 @@ pub enum Error {
     /// non-JSON type (e.g. the plain-text block height).
     #[error("decode error: {0}")]
     Decode(String),
+    /// Headers from the server failed validation: a block that doesn't hash
+    /// to its id, fails its proof of work, or doesn't link to its parent, or
+    /// a reorg deeper than the headers a [`crate::HeaderChain`] has stored
+    /// (or the blocks a [`crate::Client::subscribe_blocks`] stream follows).
+    #[error("invalid chain: {0}")]
+    InvalidChain(String),
+    /// The server accepted a broadcast but returned a txid other than the one
+    /// computed locally from the transaction
+    /// ([`crate::Client::broadcast_and_confirm`]).
+    #[error("server returned txid {actual}, expected {expected}")]
+    TxidMismatch {
+        /// The txid computed from the transaction hex.
+        expected: String,
+        /// The txid the server returned.
+        actual: String,
+    },
+    /// The server doesn't implement an optional endpoint (it answered `404`
+    /// to the endpoint itself), e.g. package broadcast or mempool acceptance
+    /// tests on an older Esplora.
+    #[error("{endpoint} is not supported by {url}")]
+    Unsupported {
+        /// The endpoint, e.g. `POST txs/test`.
+        endpoint: &'static str,
+        /// The request URL that answered `404`.
+        url: String,
+    },
+    /// The client is in [offline mode](crate::disk_cache::DiskCacheConfig::offline)
+    /// and the response isn't cached, or the request (e.g. a broadcast)
+    /// can't be served from a cache at all.
+    #[error("offline and not cached: {url}")]
+    OfflineMiss {
+        /// The request URL that would have been sent.
+        url: String,
+    },
+    /// The client was misconfigured (e.g. an invalid header passed to
+    /// [`crate::ClientBuilder`]).
+    #[error("configuration error: {0}")]
+    Config(String),
+    /// The server did not send the response headers or body within the
+    /// configured [`crate::ClientBuilder::read_timeout`].
+    #[error("read timed out after {timeout:?} from {url}")]
+    Timeout {
+        /// The request URL that timed out.
+        url: String,
+        /// The read timeout that elapsed.
+        timeout: std::time::Duration,
+    },
+    /// A request failed on every attempt allowed by the client's
+    /// [`crate::RetryPolicy`]. `attempts` records each try in order (the last
+    /// one included); `last` is the final attempt's error. Only produced when
+    /// more than one attempt was made — a single failure is returned as-is.
+    #[error("gave up after {} attempts: {last}", attempts.len())]
+    RetriesExhausted {
+        /// Every attempt, in order.
+        attempts: Vec<crate::retry::RetryAttempt>,
+        /// The error from the final attempt.
+        last: Box<Error>,
+    },
+    /// A transport, URL or JSON error from a request that several callers
+    /// were waiting on at once (see [`crate::Client`]'s request coalescing).
+    /// Those errors can't be copied, so each waiter gets a handle to the one
+    /// that occurred; every other kind of error is copied as-is. Whether a
+    /// call was coalesced depends on timing, so use [`Error::root`] rather
+    /// than matching this variant.
+    #[error("{0}")]
+    Shared(std::sync::Arc<Error>),
 }
 
 impl Error {
+    /// The error underneath any [`Error::RetriesExhausted`] and
+    /// [`Error::Shared`] wrappers: the same variant whether the call ran
+    /// alone, was coalesced with others, or was retried.
+    ///
+    /// ```
+    /// # fn demo(err: esplora_rs::Error) {
+    /// use esplora_rs::Error;
+    ///
+    /// match err.root() {
+    ///     Error::Reqwest(e) if e.is_connect() => println!("server unreachable"),
+    ///     Error::Http { status: 404, .. } => println!("not found"),
+    ///     other => println!("{other}"),
+    /// }
+    /// # }
+    /// ```
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::RetriesExhausted { last, .. } => last.root(),
+            Error::Shared(e) => e.root(),
+            other => other,
+        }
+    }
+
+    /// Whether a retry might succeed: `429`, `5xx`, connect failures and
+    /// timeouts. `4xx` responses, auth and decode errors are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::RateLimited { .. } | Error::Timeout { .. } => true,
+            Error::Http { status, .. } => *status >= 500,
+            Error::Reqwest(e) => e.is_connect() || e.is_timeout(),
+            Error::RetriesExhausted { last, .. } => last.is_retryable(),
+            Error::Shared(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Whether the request failed before reaching the server (a connect
+    /// error), so sending it elsewhere can't duplicate it.
+    pub(crate) fn is_unsent(&self) -> bool {
+        match self {
+            Error::Reqwest(e) => e.is_connect(),
+            Error::Shared(e) => e.is_unsent(),
+            _ => false,
+        }
+    }
+
+    /// Whether the server refused the request's credentials as expired or
+    /// revoked: a `401`, or a `403` whose body mentions an expired or invalid
+    /// token. The client retries such a request once with fresh credentials
+    /// from its [`crate::TokenProvider`].
+    pub fn is_auth_rejected(&self) -> bool {
+        match self {
+            Error::Http { status: 401, .. } => true,
+            Error::Http {
+                status: 403, body, ..
+            } => {
+                let body = body.to_ascii_lowercase();
+                body.contains("expired") || body.contains("invalid_token")
+            }
+            Error::Shared(e) => e.is_auth_rejected(),
+            _ => false,
+        }
+    }
+
+    /// Why a [`crate::Client::broadcast_tx`] was refused, parsed from the
+    /// `400` response body. `None` for any other error.
+    pub fn broadcast_rejection(&self) -> Option<crate::broadcast::RpcRejection> {
+        match self {
+            Error::Http {
+                status: 400, body, ..
+            } => Some(crate::broadcast::RpcRejection::parse(body)),
+            Error::RetriesExhausted { last, .. } => last.broadcast_rejection(),
+            Error::Shared(e) => e.broadcast_rejection(),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code behind this error, if it came from a response.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Error::Http { status, .. } => Some(*status),
+            Error::RateLimited { .. } => Some(429),
+            Error::Reqwest(e) => e.status().map(|s| s.as_u16()),
+            Error::RetriesExhausted { last, .. } => last.status(),
+            Error::Shared(e) => e.status(),
+            _ => None,
+        }
+    }
+
+    /// The server's `Retry-After` in seconds, for a rate-limited response.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            Error::RateLimited { retry_after, .. } => *retry_after,
+            Error::RetriesExhausted { last, .. } => last.retry_after(),
+            Error::Shared(e) => e.retry_after(),
+            _ => None,
+        }
+    }
+
+    /// A copy of `err` for one waiter of a coalesced request: an equal value
+    /// where the variant allows it, otherwise [`Error::Shared`].
+    pub(crate) fn shared(err: &std::sync::Arc<Error>) -> Self {
+        err.try_clone()
+            .unwrap_or_else(|| Error::Shared(std::sync::Arc::clone(err)))
+    }
+
+    /// A copy of `self`, or `None` if it holds (or, after retries, ends in) a
+    /// `reqwest`, `url` or `serde_json` error, which aren't `Clone`.
+    fn try_clone(&self) -> Option<Self> {
+        Some(match self {
+            Error::Reqwest(_) | Error::Url(_) | Error::SerdeJson(_) => return None,
+            Error::Auth {
+                error,
+                error_description,
+            } => Error::Auth {
+                error: error.clone(),
+                error_description: error_description.clone(),
+            },
+            Error::EnvVar(name) => Error::EnvVar(name.clone()),
+            Error::Http { status, url, body } => Error::Http {
+                status: *status,
+                url: url.clone(),
+                body: body.clone(),
+            },
+            Error::RateLimited {
+                url,
+                retry_after,
+                body,
+            } => Error::RateLimited {
+                url: url.clone(),
+                retry_after: *retry_after,
+                body: body.clone(),
+            },
+            Error::Decode(msg) => Error::Decode(msg.clone()),
+            Error::InvalidChain(msg) => Error::InvalidChain(msg.clone()),
+            Error::TxidMismatch { expected, actual } => Error::TxidMismatch {
+                expected: expected.clone(),
+                actual: actual.clone(),
+            },
+            Error::Unsupported { endpoint, url } => Error::Unsupported {
+                endpoint,
+                url: url.clone(),
+            },
+            Error::OfflineMiss { url } => Error::OfflineMiss { url: url.clone() },
+            Error::Config(msg) => Error::Config(msg.clone()),
+            Error::Timeout { url, timeout } => Error::Timeout {
+                url: url.clone(),
+                timeout: *timeout,
+            },
+            Error::RetriesExhausted { attempts, last } => Error::RetriesExhausted {
+                attempts: attempts.clone(),
+                last: Box::new(last.try_clone()?),
+            },
+            Error::Shared(err) => Error::Shared(err.clone()),
+        })
+    }
+
+    /// An [`Error::Auth`] without a server-provided description.
+    pub(crate) fn auth(error: impl Into<String>) -> Self {
+        Error::Auth {
+            error: error.into(),
+            error_description: None,
+        }
+    }
+
+    /// Reports a `404` from an optional endpoint as [`Error::Unsupported`],
+    /// also when it ended a run of retries; any other error is returned
+    /// unchanged.
+    pub(crate) fn into_unsupported(self, endpoint: &'static str) -> Self {
+        match self.root() {
+            Error::Http {
+                status: 404, url, ..
+            } => Error::Unsupported {
+                endpoint,
+                url: url.clone(),
+            },
+            _ => self,
+        }
+    }
+
     /// Classify a non-2xx response into [`Error::RateLimited`] (for `429`) or
     /// [`Error::Http`]. `retry_after` should be parsed from the `Retry-After`
     /// header before the body is consumed.
@@ -0,0 +1,203 @@
+//! Multi-backend failover.
+//!
+//! A [`crate::Client`] always holds an ordered list of backends: the primary
+//! `base_url` it was built with, followed by any added with
+//! [`crate::ClientBuilder::fallback`]. Each request goes to the first backend
+//! that isn't cooling down; on a connect error, timeout, `5xx` or `429`
+//! ([`Error::is_retryable`]) that backend is put in a cooldown and the request
+//! moves on to the next. Other failures (`404`, `400`, decode errors) are
+//! returned straight away — another backend would answer the same.
+//!
+//! Broadcasts are the exception: a timeout or `5xx` may come after the node
+//! accepted the transaction, so they only move on after a connect error
+//! (nothing was sent), unless the policy sets
+//! [`crate::RetryPolicy::retry_broadcast`].
+//!
+//! Which backend served a response is logged at `debug` and can be captured
+//! with [`served_by`].
+
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::auth::TokenProvider;
+use crate::Error;
+
+/// How long a failing backend is skipped by default.
+pub const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+tokio::task_local! {
+    static SERVED_BY: RefCell<Vec<String>>;
+}
+
+/// Runs `fut` and returns its output together with the backend (its base URL)
+/// that served each request `fut` made, in order. A method that makes several
+/// requests (e.g. [`crate::Client::get_waterfalls_all`]) reports one entry per
+/// request; a request that failed everywhere reports none.
+///
+/// ```no_run
+/// # async fn demo(client: esplora_rs::Client) -> Result<(), esplora_rs::Error> {
+/// use esplora_rs::failover::served_by;
+///
+/// let (height, backends) = served_by(client.get_tip_height()).await;
+/// println!("tip {} from {:?}", height?, backends);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn served_by<F: Future>(fut: F) -> (F::Output, Vec<String>) {
+    SERVED_BY
+        .scope(RefCell::new(Vec::new()), async move {
+            let output = fut.await;
+            let served = SERVED_BY.with(|s| s.take());
+            (output, served)
+        })
+        .await
+}
+
+/// A point-in-time view of one backend, from [`crate::Client::backend_status`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendStatus {
+    /// The backend's base URL.
+    pub base_url: String,
+    /// How much longer the backend is skipped, if it is cooling down.
+    pub cooldown_remaining: Option<Duration>,
+}
+
+#[derive(Debug)]
+pub(crate) struct Backend {
+    pub(crate) base_url: Url,
+    /// `None` for an unauthenticated backend.
+    pub(crate) auth: Option<Arc<dyn TokenProvider>>,
+}
+
+/// The ordered backend list plus each one's cooldown deadline. Shared by all
+/// clones of a client, so one task's failure steers the others away too.
+#[derive(Debug)]
+pub(crate) struct Backends {
+    backends: Vec<Backend>,
+    cooldown_until: Mutex<Vec<Option<Instant>>>,
+    cooldown: Duration,
+}
+
+impl Backends {
+    pub(crate) fn new(backends: Vec<Backend>, cooldown: Duration) -> Self {
+        let cooldown_until = Mutex::new(vec![None; backends.len()]);
+        Self {
+            backends,
+            cooldown_until,
+            cooldown,
+        }
+    }
+
+    pub(crate) fn get(&self, index: usize) -> &Backend {
+        &self.backends[index]
+    }
+
+    /// Resolves `path` plus `query` against every backend, in list order.
+    pub(crate) fn urls(&self, path: &str, query: &[(String, String)]) -> Result<Vec<Url>, Error> {
+        self.backends
+            .iter()
+            .map(|b| {
+                let mut url = b.base_url.join(path)?;
+                if !query.is_empty() {
+                    url.query_pairs_mut()
+                        .extend_pairs(query.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+                }
+                Ok(url)
+            })
+            .collect()
+    }
+
+    /// The base URL of the backend `url` was resolved against, and `url`'s
+    /// path relative to it. With nested base paths on one host (`/api/` and
+    /// `/api/testnet/`), the longest that matches wins. A URL no backend
+    /// produced gives `""` and its whole path.
+    pub(crate) fn locate<'a>(&'a self, url: &'a Url) -> (&'a str, &'a str) {
+        let path = url.path();
+        self.backends
+            .iter()
+            .filter(|b| b.base_url.origin() == url.origin())
+            .filter_map(|b| Some((b.base_url.as_str(), path.strip_prefix(b.base_url.path())?)))
+            .min_by_key(|(_, relative)| relative.len())
+            .unwrap_or(("", path))
+    }
+
+    pub(crate) fn status(&self) -> Vec<BackendStatus> {
+        let now = Instant::now();
+        let until = self.cooldown_until.lock().expect("cooldown lock poisoned");
+        self.backends
+            .iter()
+            .zip(until.iter())
+            .map(|(b, until)| BackendStatus {
+                base_url: b.base_url.to_string(),
+                cooldown_remaining: until
+                    .and_then(|t| t.checked_duration_since(now))
+                    .filter(|d| !d.is_zero()),
+            })
+            .collect()
+    }
+
+    /// Backend indices in the order to try them: those not cooling down,
+    /// in list order, then the cooling ones (soonest-available first) so a
+    /// request still gets a chance when every backend is marked down.
+    fn order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let until = self.cooldown_until.lock().expect("cooldown lock poisoned");
+        let (mut ready, mut cooling): (Vec<usize>, Vec<usize>) =
+            (0..self.backends.len()).partition(|&i| !matches!(until[i], Some(t) if t > now));
+        cooling.sort_by_key(|&i| until[i]);
+        ready.append(&mut cooling);
+        ready
+    }
+
+    fn mark_down(&self, index: usize) {
+        let mut until = self.cooldown_until.lock().expect("cooldown lock poisoned");
+        until[index] = Some(Instant::now() + self.cooldown);
+    }
+
+    fn mark_up(&self, index: usize) {
+        let mut until = self.cooldown_until.lock().expect("cooldown lock poisoned");
+        until[index] = None;
+    }
+
+    /// Runs `op` against each backend in failover order until one succeeds or
+    /// fails with an error another backend wouldn't fix. Unless `resend`, a
+    /// request that may have reached the server (anything but a connect
+    /// error) isn't sent to another backend.
+    pub(crate) async fn run<T, F, Fut>(&self, resend: bool, mut op: F) -> Result<T, Error>
+    where
+        F: FnMut(usize) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let order = self.order();
+        let last = order.len() - 1;
+        for (n, index) in order.into_iter().enumerate() {
+            match op(index).await {
+                Ok(value) => {
+                    self.mark_up(index);
+                    let base = self.backends[index].base_url.as_str();
+                    debug!(target: "esplora_rs", "served by {}", base);
+                    let _ = SERVED_BY.try_with(|s| s.borrow_mut().push(base.to_string()));
+                    return Ok(value);
+                }
+                Err(err) if err.is_retryable() => {
+                    self.mark_down(index);
+                    if n == last || !(resend || err.is_unsent()) {
+                        return Err(err);
+                    }
+                    warn!(
+                        target: "esplora_rs",
+                        "backend {} failed ({}), cooling down for {:?} and failing over",
+                        self.backends[index].base_url, err, self.cooldown
+                    );
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        unreachable!("backend list is never empty")
+    }
+}
@@ -0,0 +1,137 @@
+//! Hex, base64 and SHA-256 helpers for the hashing done client-side
+//! (scripthashes, merkle proofs, block headers) and for auth headers.
+//!
+//! Bitcoin displays hashes byte-reversed relative to how they are hashed and
+//! serialized, so the conversions here are explicit about which order they
+//! take and return.
+
+use sha2::{Digest, Sha256};
+
+use crate::Error;
+
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// `SHA256(SHA256(data))`, as used for txids, block hashes and merkle nodes.
+pub(crate) fn sha256d(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+/// Decodes a hex string. `what` names the value in the error message.
+pub(crate) fn decode_hex(hex: &str, what: &str) -> Result<Vec<u8>, Error> {
+    hex.trim()
+        .as_bytes()
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| {
+            std::str::from_utf8(pair)
+                .ok()
+                .filter(|pair| pair.len() == 2 && pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| Error::Decode(format!("invalid {what} hex at offset {}", i * 2)))
+        })
+        .collect()
+}
+
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Standard padded base64 (RFC 4648 §4), as HTTP Basic auth wants.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    base64(
+        bytes,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/",
+        true,
+    )
+}
+
+/// Unpadded URL-safe base64 (RFC 4648 §5), as JWTs use.
+pub(crate) fn encode_base64url(bytes: &[u8]) -> String {
+    base64(
+        bytes,
+        b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_",
+        false,
+    )
+}
+
+fn base64(bytes: &[u8], alphabet: &[u8; 64], pad: bool) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(char::from(alphabet[(n >> (18 - 6 * i) & 0x3f) as usize]));
+            } else if pad {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes a 32-byte hash from its byte-reversed display hex (a txid or block
+/// hash as Esplora prints it) into internal byte order.
+pub(crate) fn decode_display_hash(hex: &str, what: &str) -> Result<[u8; 32], Error> {
+    let mut hash: [u8; 32] = decode_hex(hex, what)?
+        .try_into()
+        .map_err(|_| Error::Decode(format!("invalid {what}: expected 32 bytes")))?;
+    hash.reverse();
+    Ok(hash)
+}
+
+/// Renders a 32-byte hash in internal byte order as byte-reversed display hex.
+pub(crate) fn display_hash(hash: &[u8; 32]) -> String {
+    let mut reversed = *hash;
+    reversed.reverse();
+    encode_hex(&reversed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha256_known_vectors() {
+        assert_eq!(
+            encode_hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            encode_hex(&sha256d(b"hello")),
+            "9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50"
+        );
+    }
+
+    #[test]
+    fn hex_round_trips_and_rejects_garbage() {
+        assert_eq!(decode_hex("00ff7a", "x").unwrap(), vec![0x00, 0xff, 0x7a]);
+        assert_eq!(encode_hex(&[0x00, 0xff, 0x7a]), "00ff7a");
+        assert!(decode_hex("abc", "x").is_err());
+        assert!(decode_hex("zz", "x").is_err());
+        assert!(decode_hex("+f", "x").is_err());
+        assert!(decode_display_hash("00", "txid").is_err());
+    }
+
+    #[test]
+    fn base64_rfc4648_vectors() {
+        let cases = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(encode_base64(input.as_bytes()), expected);
+        }
+        assert_eq!(encode_base64(&[0xfb, 0xff]), "+/8=");
+        assert_eq!(encode_base64url(&[0xfb, 0xff]), "-_8");
+    }
+}
@@ -0,0 +1,262 @@
+//! [`BlockHeader`]: the 80-byte block header, decoded and checked locally.
+//!
+//! [`crate::Client::get_block_header`] returns the raw header as hex. Parsing
+//! it here lets a caller recompute the block hash, check its proof of work,
+//! and confirm that the server's [`Block`] JSON describes the same block —
+//! all without the `bitcoin` crate.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::hashes::{decode_display_hash, decode_hex, display_hash, sha256d};
+use crate::{Block, Error};
+
+/// Length of a serialized block header.
+pub const HEADER_LEN: usize = 80;
+
+/// A decoded block header. Hashes are display hex (byte-reversed), matching
+/// the rest of the API.
+///
+/// The header is immutable: every accessor reads the same 80 bytes the hash
+/// is computed from, so the fields can't drift from [`Self::block_hash`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockHeader {
+    raw: [u8; HEADER_LEN],
+}
+
+impl BlockHeader {
+    /// Decodes a header from hex, as returned by
+    /// [`crate::Client::get_block_header`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if `hex` is not exactly 80 bytes of hex.
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        let bytes = decode_hex(hex, "block header")?;
+        let raw: [u8; HEADER_LEN] = bytes.as_slice().try_into().map_err(|_| {
+            Error::Decode(format!(
+                "block header is {} bytes, expected {HEADER_LEN}",
+                bytes.len()
+            ))
+        })?;
+        Ok(Self { raw })
+    }
+
+    /// Rebuilds the header a server's [`Block`] JSON describes. Its
+    /// [`Self::block_hash`] equals `block.id` only if the JSON is faithful,
+    /// which is how [`crate::HeaderChain`] checks listings from
+    /// [`crate::Client::get_blocks`] without fetching each raw header.
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if a hash field is not 32 bytes of hex or
+    /// the timestamp does not fit in 32 bits.
+    pub fn from_block(block: &Block) -> Result<Self, Error> {
+        let prev = match &block.previousblockhash {
+            Some(hash) => decode_display_hash(hash, "previousblockhash")?,
+            None => [0u8; 32],
+        };
+        let time = u32::try_from(block.timestamp).map_err(|_| {
+            Error::Decode(format!("block timestamp {} overflows u32", block.timestamp))
+        })?;
+        let mut raw = [0u8; HEADER_LEN];
+        raw[0..4].copy_from_slice(&block.version.to_le_bytes());
+        raw[4..36].copy_from_slice(&prev);
+        raw[36..68].copy_from_slice(&decode_display_hash(&block.merkle_root, "merkle_root")?);
+        raw[68..72].copy_from_slice(&time.to_le_bytes());
+        raw[72..76].copy_from_slice(&block.bits.to_le_bytes());
+        raw[76..80].copy_from_slice(&block.nonce.to_le_bytes());
+        Ok(Self { raw })
+    }
+
+    /// The block version (`nVersion`, a signed `int32`).
+    pub fn version(&self) -> i32 {
+        i32::from_le_bytes(self.raw[0..4].try_into().expect("4 bytes"))
+    }
+
+    /// The previous block's hash; all zeros for genesis.
+    pub fn prev_blockhash(&self) -> String {
+        self.hash_at(4)
+    }
+
+    /// The merkle root of the block's transactions.
+    pub fn merkle_root(&self) -> String {
+        self.hash_at(36)
+    }
+
+    /// The block timestamp (Unix seconds).
+    pub fn time(&self) -> u32 {
+        self.u32_at(68)
+    }
+
+    /// The difficulty target in compact form.
+    pub fn bits(&self) -> u32 {
+        self.u32_at(72)
+    }
+
+    /// The nonce.
+    pub fn nonce(&self) -> u32 {
+        self.u32_at(76)
+    }
+
+    /// The block hash: double SHA-256 of the header, as display hex.
+    pub fn block_hash(&self) -> String {
+        display_hash(&self.hash_bytes())
+    }
+
+    /// The target `bits` expands to, as a big-endian 256-bit number, or
+    /// `None` if `bits` encodes a negative, zero or overflowing target.
+    pub fn target(&self) -> Option<[u8; 32]> {
+        let bits = self.bits();
+        let exponent = (bits >> 24) as usize;
+        let mantissa = bits & 0x007f_ffff;
+        if mantissa == 0 || bits & 0x0080_0000 != 0 {
+            return None;
+        }
+        let mut target = [0u8; 32];
+        let mantissa = mantissa.to_be_bytes(); // [0, m2, m1, m0]
+        for (i, byte) in mantissa[1..].iter().enumerate() {
+            // Byte `i` of the mantissa sits `exponent - 1 - i` bytes above the
+            // least significant byte; bytes shifted below it are dropped.
+            let Some(shift) = exponent.checked_sub(1 + i) else {
+                continue;
+            };
+            if shift >= 32 {
+                if *byte != 0 {
+                    return None;
+                }
+                continue;
+            }
+            target[31 - shift] = *byte;
+        }
+        (target != [0u8; 32]).then_some(target)
+    }
+
+    /// Whether the block hash meets the target its own `bits` claim.
+    ///
+    /// This proves work was done for *some* difficulty; it does not check
+    /// that `bits` is the difficulty the chain requires at this height, so a
+    /// header with a trivially easy target passes. Compare `bits` against the
+    /// expected value to rule that out.
+    pub fn check_pow(&self) -> bool {
+        let Some(target) = self.target() else {
+            return false;
+        };
+        let mut hash = self.hash_bytes();
+        hash.reverse(); // internal little-endian → big-endian number
+        hash <= target
+    }
+
+    /// Whether `block` (the server's JSON view) describes this header: same
+    /// hash, version, parent, merkle root, time, bits and nonce.
+    pub fn matches_block(&self, block: &Block) -> bool {
+        let genesis_parent = "0".repeat(64);
+        let prev = block
+            .previousblockhash
+            .as_deref()
+            .unwrap_or(&genesis_parent);
+        self.block_hash().eq_ignore_ascii_case(&block.id)
+            && self.version() == block.version
+            && self.prev_blockhash().eq_ignore_ascii_case(prev)
+            && self.merkle_root().eq_ignore_ascii_case(&block.merkle_root)
+            && u64::from(self.time()) == block.timestamp
+            && self.bits() == block.bits
+            && self.nonce() == block.nonce
+    }
+
+    fn hash_bytes(&self) -> [u8; 32] {
+        sha256d(&self.raw)
+    }
+
+    fn u32_at(&self, i: usize) -> u32 {
+        u32::from_le_bytes(self.raw[i..i + 4].try_into().expect("4 bytes"))
+    }
+
+    fn hash_at(&self, i: usize) -> String {
+        display_hash(&self.raw[i..i + 32].try_into().expect("32 bytes"))
+    }
+}
+
+impl FromStr for BlockHeader {
+    type Err = Error;
+
+    fn from_str(hex: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(hex)
+    }
+}
+
+impl fmt::Display for BlockHeader {
+    /// Formats the header as its 80-byte hex serialization.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.raw.iter().try_for_each(|b| write!(f, "{b:02x}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mainnet block 100000.
+    const HEADER: &str = "0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710";
+    const HASH: &str = "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506";
+
+    #[test]
+    fn parses_and_hashes_block_100000() {
+        let header: BlockHeader = HEADER.parse().unwrap();
+        assert_eq!(header.version(), 1);
+        assert_eq!(
+            header.prev_blockhash(),
+            "000000000002d01c1fccc21636b607dfd930d31d01c3a62104612a1719011250"
+        );
+        assert_eq!(
+            header.merkle_root(),
+            "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766"
+        );
+        assert_eq!(header.time(), 1_293_623_863);
+        assert_eq!(header.bits(), 0x1b04_864c);
+        assert_eq!(header.nonce(), 274_148_111);
+        assert_eq!(header.block_hash(), HASH);
+        assert_eq!(header.to_string(), HEADER);
+        assert!(header.check_pow());
+    }
+
+    fn with_bits(bits: u32) -> BlockHeader {
+        let mut header: BlockHeader = HEADER.parse().unwrap();
+        header.raw[72..76].copy_from_slice(&bits.to_le_bytes());
+        header
+    }
+
+    #[test]
+    fn expands_compact_targets() {
+        let target = |bits| with_bits(bits).target();
+        // Mainnet pow limit: 0x00000000ffff0000...
+        let mut limit = [0u8; 32];
+        limit[4] = 0xff;
+        limit[5] = 0xff;
+        assert_eq!(target(0x1d00_ffff), Some(limit));
+        // Small exponents shift the mantissa right.
+        let mut small = [0u8; 32];
+        small[31] = 0x12;
+        assert_eq!(target(0x0112_3456), Some(small));
+        // Negative, zero and overflowing encodings are rejected.
+        assert_eq!(target(0x0480_0000), None);
+        assert_eq!(target(0x1d00_0000), None);
+        assert_eq!(target(0x2301_0000), None);
+    }
+
+    #[test]
+    fn tampered_header_fails_pow() {
+        let mut header: BlockHeader = HEADER.parse().unwrap();
+        header.raw[76] ^= 1; // flip a nonce bit
+        assert!(!header.check_pow());
+        // Claiming a harder target than the work done changes the hash too,
+        // but either way it must not pass.
+        assert!(!with_bits(0x1a00_ffff).check_pow());
+    }
+
+    #[test]
+    fn rejects_wrong_length() {
+        assert!(matches!(
+            BlockHeader::from_hex(&HEADER[..158]),
+            Err(Error::Decode(_))
+        ));
+    }
+}
@@ -13,22 +13,31 @@
 //!   (e.g. `https://blockstream.info/testnet/api`).
 //! - [`Client::new`] — **enterprise** instances that require an OAuth Bearer
 //!   token (e.g. `https://enterprise.blockstream.info/testnet/api`).
+//! - [`Client::with_credentials`] / [`Client::with_credentials_and_token_url`]
+//!   — enterprise with credentials passed in rather than read from the
+//!   environment.
+//! - [`Client::from_env`] — public or enterprise, chosen by
+//!   `ESPLORA_AUTH_MODE` and whether credentials are set.
+//! - [`Client::builder`] — either of the above with custom timeouts,
+//!   `User-Agent`, default headers, HTTP version, or an injected
+//!   `reqwest::Client`. Both constructors above are shorthands for it.
 //!
 //! # Environment variables (read by this crate)
 //! ⚠️ The **base URL is a parameter**, but the **enterprise credentials are read
-//! implicitly from the process environment** by [`Client::new`]:
+//! implicitly from the process environment** by [`Client::new`] and
+//! [`Client::from_env`]:
 //!
 //! | Variable | Read by | Purpose |
 //! |---|---|---|
-//! | `ESPLORA_CLIENT_ID` | [`Client::new`] | OAuth `client_id` |
-//! | `ESPLORA_CLIENT_SECRET` | [`Client::new`] | OAuth `client_secret` |
+//! | `ESPLORA_CLIENT_ID` | [`Client::new`], [`Client::from_env`] | OAuth `client_id` |
+//! | `ESPLORA_CLIENT_SECRET` | [`Client::new`], [`Client::from_env`] | OAuth `client_secret` |
+//! | `ESPLORA_AUTH_MODE` | [`Client::from_env`] | `auto` \| `public` \| `enterprise` |
 //! | `ESPLORA_TEST_LIVE` | test suite only | gate for the crate's live tests |
 //!
 //! Any `APP_*` variables (`APP_ESPLORA_URL`, `APP_CHAIN_BACKEND`, …) belong to
 //! the **implementing program**, not this crate — that program reads them and
-//! passes the resulting URL in as `base_url`. *(An explicit
-//! `with_credentials(url, id, secret)` constructor that removes the env read is
-//! tracked in `docs/TODO.md` as E4.)*
+//! passes the resulting URL in as `base_url`. [`Client::with_credentials`]
+//! reads nothing from the environment.
 //!
 //! # Waterfalls / QuickSync
 //! [`Client::get_waterfalls`] / [`Client::get_waterfalls_all`] hit
@@ -40,23 +49,69 @@
 //! [Waterfalls / QuickSync]: https://github.com/Blockstream/waterfalls
 
 use std::env;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 
 pub mod auth;
+pub mod batch;
+pub mod broadcast;
+pub mod builder;
+pub mod cache;
+pub mod chain;
+pub mod disk_cache;
 pub mod error;
+pub mod failover;
+mod hashes;
+pub mod header;
+pub mod merkle;
 pub mod models;
-
-pub use auth::Auth;
+pub mod paginate;
+pub mod ratelimit;
+mod rawtx;
+pub mod retry;
+pub mod script;
+mod singleflight;
+pub mod subscribe;
+pub mod usage;
+pub mod watch;
+
+pub use auth::{
+    Auth, ClientAuth, JwtSigner, OAuthConfig, StaticHeader, TokenEndpoint, TokenProvider,
+};
+pub use broadcast::{
+    BroadcastOutcome, BroadcastPolicy, BroadcastRejection, ConfirmTarget, OutPoint, RpcRejection,
+};
+pub use builder::{ClientBuilder, HttpVersion};
+pub use cache::{CacheConfig, CacheStats};
+pub use chain::{ChainEvent, HeaderChain, HeaderEntry, HeaderStore, MemoryStore};
+pub use disk_cache::DiskCacheConfig;
 pub use error::Error;
+pub use header::BlockHeader;
 pub use models::{
-    AddressInfo, AssetInfo, Block, BlockStatus, FeeEstimates, Mempool, Outspend, RecentTx,
-    Transaction, TxSeen, TxStatus, Utxo, WaterfallResponse,
+    AddressInfo, AssetInfo, Block, BlockStatus, FeeEstimates, Mempool, MempoolAcceptResult,
+    MerkleProof, Outspend, PackageFees, PackageResult, PackageTxResult, RecentTx, Transaction,
+    TxSeen, TxStatus, Utxo, WaterfallResponse,
 };
+pub use paginate::Paged;
+pub use ratelimit::RateLimit;
+pub use retry::{RetryAttempt, RetryPolicy};
+pub use script::scripthash;
+pub use subscribe::{BlockEvent, BlockSubscription};
+pub use usage::{CreditAlert, EndpointUsage, UsageStats};
+pub use watch::{TxEvent, TxEvents, TxWatcher};
 
 use bytes::Bytes;
-use reqwest::header::{ACCEPT, AUTHORIZATION, RETRY_AFTER};
+use cache::ResponseCache;
+use disk_cache::DiskCache;
+use failover::{BackendStatus, Backends};
+use ratelimit::RateLimiter;
+use reqwest::header::{HeaderMap, ACCEPT, RETRY_AFTER};
 use reqwest::Client as ReqwestClient;
-use tracing::{debug, error, info, trace};
+use singleflight::SingleFlight;
+use tracing::{debug, error, info, trace, warn};
 use url::Url;
+use usage::UsageTracker;
 
 const DEFAULT_TOKEN_URL: &str =
     "https://login.blockstream.com/realms/blockstream-public/protocol/openid-connect/token";
@@ -65,8 +120,18 @@ const DEFAULT_TOKEN_URL: &str =
 #[derive(Debug, Clone)]
 pub struct Client {
     http_client: ReqwestClient,
-    base_url: Url,
-    auth: Auth,
+    backends: Arc<Backends>,
+    read_timeout: Option<Duration>,
+    retry: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    batch_concurrency: usize,
+    cache: Option<Arc<ResponseCache>>,
+    disk_cache: Option<Arc<DiskCache>>,
+    in_flight: Arc<SingleFlight>,
+    usage: Arc<UsageTracker>,
+    /// Set on the view [`Self::following_chain`] returns: reads skip cached
+    /// reorg-sensitive entries.
+    follows_chain: bool,
 }
 
 /// Ensure the base URL ends with `/` so [`Url::join`] appends path segments
@@ -80,14 +145,27 @@ fn ensure_base_slash(url: &str) -> String {
     }
 }
 
-/// Parse the `Retry-After` header (delta-seconds form) from a response, if
-/// present and numeric. HTTP-date form is not parsed (returns `None`).
+/// Parse the `Retry-After` header from a response, if present: either
+/// delta-seconds (`"120"`) or an HTTP-date (`"Wed, 21 Oct 2015 07:28:00 GMT"`),
+/// the latter converted to seconds from now (`0` if already past).
 fn retry_after_secs(response: &reqwest::Response) -> Option<u64> {
     response
         .headers()
         .get(RETRY_AFTER)
         .and_then(|v| v.to_str().ok())
-        .and_then(|s| s.trim().parse::<u64>().ok())
+        .and_then(|s| parse_retry_after(s, chrono::Utc::now()))
+}
+
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<u64> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(secs);
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let secs = (date.with_timezone(&chrono::Utc) - now)
+        .num_seconds()
+        .max(0);
+    u64::try_from(secs).ok()
 }
 
 impl Client {
@@ -104,9 +182,104 @@ impl Client {
             .map_err(|_| Error::EnvVar("ESPLORA_CLIENT_ID".to_string()))?;
         let client_secret = env::var("ESPLORA_CLIENT_SECRET")
             .map_err(|_| Error::EnvVar("ESPLORA_CLIENT_SECRET".to_string()))?;
-        let token_url = Url::parse(DEFAULT_TOKEN_URL).expect("Failed to parse default token URL");
+        Self::with_credentials(base_url, client_id, client_secret)
+    }
+
+    /// Creates a client for an enterprise API URL with explicit OAuth
+    /// credentials and the default Blockstream token endpoint. Unlike
+    /// [`Self::new`], nothing is read from the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid.
+    pub fn with_credentials(
+        base_url: &str,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Result<Self, Error> {
+        Self::with_credentials_and_token_url(base_url, client_id, client_secret, DEFAULT_TOKEN_URL)
+    }
+
+    /// Like [`Self::with_credentials`], but fetches tokens from `token_url`
+    /// (e.g. a non-Blockstream OAuth provider or a test server).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either URL is invalid.
+    pub fn with_credentials_and_token_url(
+        base_url: &str,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+        token_url: &str,
+    ) -> Result<Self, Error> {
+        Self::from_parts(
+            base_url,
+            Url::parse(token_url)?,
+            client_id.into(),
+            client_secret.into(),
+        )
+    }
 
-        Self::from_parts(base_url, token_url, client_id, client_secret)
+    /// Creates a public or enterprise client for `base_url`, as the
+    /// environment says:
+    ///
+    /// | `ESPLORA_AUTH_MODE` | Client |
+    /// |---|---|
+    /// | `auto` (or unset) | enterprise if `ESPLORA_CLIENT_ID` and `ESPLORA_CLIENT_SECRET` are both set, else public |
+    /// | `public` | public, even if credentials are set |
+    /// | `enterprise` | enterprise; fails if a credential is missing |
+    ///
+    /// A variable set to the empty string counts as unset. The client itself
+    /// comes from [`Self::new_public`] or [`Self::with_credentials`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::EnvVar`] naming the missing credential in
+    /// `enterprise` mode, [`Error::Config`] for an unknown
+    /// `ESPLORA_AUTH_MODE`, or an error if the URL is invalid.
+    pub fn from_env(base_url: &str) -> Result<Self, Error> {
+        Self::from_lookup(base_url, |name| env::var(name).ok())
+    }
+
+    /// [`Self::from_env`] over any variable source, so the rules can be
+    /// tested without touching the process environment.
+    fn from_lookup(base_url: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<Self, Error> {
+        let var = |name: &str| lookup(name).filter(|value| !value.is_empty());
+        let mode = var("ESPLORA_AUTH_MODE");
+        let enterprise = match mode.as_deref().map(str::to_ascii_lowercase).as_deref() {
+            None | Some("auto") => None,
+            Some("public") => Some(false),
+            Some("enterprise") => Some(true),
+            Some(_) => {
+                return Err(Error::Config(format!(
+                    "invalid ESPLORA_AUTH_MODE {:?} (expected auto, public or enterprise)",
+                    mode.unwrap_or_default()
+                )))
+            }
+        };
+        let client_id = var("ESPLORA_CLIENT_ID");
+        let client_secret = var("ESPLORA_CLIENT_SECRET");
+        match (enterprise, client_id, client_secret) {
+            (Some(false), ..) => Self::new_public(base_url),
+            (_, Some(id), Some(secret)) => Self::with_credentials(base_url, id, secret),
+            (Some(true), id, _) => Err(Error::EnvVar(
+                if id.is_none() {
+                    "ESPLORA_CLIENT_ID"
+                } else {
+                    "ESPLORA_CLIENT_SECRET"
+                }
+                .to_string(),
+            )),
+            (None, id, secret) => {
+                if id.is_some() || secret.is_some() {
+                    warn!(
+                        target: "esplora_rs",
+                        "only one of ESPLORA_CLIENT_ID / ESPLORA_CLIENT_SECRET is set; using the public API"
+                    );
+                }
+                Self::new_public(base_url)
+            }
+        }
     }
 
     /// Creates a new Esplora client for a public API URL.
@@ -115,19 +288,21 @@ impl Client {
     ///
     /// Returns an error if the URL is invalid.
     pub fn new_public(base_url: &str) -> Result<Self, Error> {
-        let auth = Auth::new_public();
-        // Force HTTP/1.1 to avoid issues with some servers and HTTP/2 POST requests
-        let http_client = ReqwestClient::builder()
-            .http1_only()
-            .build()
-            .map_err(Error::Reqwest)?;
-        let base_url = Url::parse(&ensure_base_slash(base_url))?;
+        Self::builder(base_url).build()
+    }
 
-        Ok(Self {
-            http_client,
-            base_url,
-            auth,
-        })
+    /// Returns a [`ClientBuilder`] for `base_url`, for configuring timeouts,
+    /// the `User-Agent`, default headers, the HTTP version, auth, or an
+    /// injected `reqwest::Client`. Without further calls it builds the same
+    /// client as [`Self::new_public`].
+    pub fn builder(base_url: &str) -> ClientBuilder {
+        ClientBuilder::new(base_url)
+    }
+
+    /// Each backend's base URL and cooldown state, primary first. See
+    /// [`failover`].
+    pub fn backend_status(&self) -> Vec<BackendStatus> {
+        self.backends.status()
     }
 
     /// Creates a new Esplora client from its constituent parts. Useful for testing.
@@ -137,47 +312,272 @@ impl Client {
         client_id: String,
         client_secret: String,
     ) -> Result<Self, Error> {
-        let auth = Auth::new(client_id, client_secret, token_url);
-        // Force HTTP/1.1 to avoid issues with some servers and HTTP/2 POST requests
-        let http_client = ReqwestClient::builder()
-            .http1_only()
+        Self::builder(base_url)
+            .auth(Auth::new(client_id, client_secret, token_url))
             .build()
-            .map_err(Error::Reqwest)?;
-        let base_url = Url::parse(&ensure_base_slash(base_url))?;
+    }
 
-        Ok(Self {
-            http_client,
-            base_url,
-            auth,
-        })
+    /// Runs `op` with `backend`'s auth headers (none without a
+    /// [`TokenProvider`]). If the server rejects them as expired or revoked
+    /// ([`Error::is_auth_rejected`]), the provider is told to drop them and
+    /// `op` runs once more with fresh ones.
+    async fn with_auth<T, F, Fut>(&self, backend: usize, mut op: F) -> Result<T, Error>
+    where
+        F: FnMut(HeaderMap) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let Some(provider) = &self.backends.get(backend).auth else {
+            return op(HeaderMap::new()).await;
+        };
+        let headers = provider.auth_headers().await?;
+        match op(headers.clone()).await {
+            Err(err) if err.is_auth_rejected() => {
+                warn!(target: "esplora_rs", "credentials rejected ({}); retrying once with fresh ones", err);
+                provider.invalidate(&headers).await;
+                op(provider.auth_headers().await?).await
+            }
+            result => result,
+        }
     }
 
-    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
-        let token = self.auth.get_token().await?;
-        let url = self.base_url.join(path)?;
-        debug!(target: "esplora_rs", "GET {}", url);
+    /// Awaits `fut` under the configured read timeout, if any. Used for both
+    /// the wait for response headers and the body read.
+    async fn read_with_timeout<T>(
+        &self,
+        url: &Url,
+        fut: impl Future<Output = Result<T, reqwest::Error>>,
+    ) -> Result<T, Error> {
+        match self.read_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, fut).await {
+                Ok(res) => Ok(res?),
+                Err(_) => Err(Error::Timeout {
+                    url: url.to_string(),
+                    timeout,
+                }),
+            },
+            None => Ok(fut.await?),
+        }
+    }
 
-        let mut req = self
-            .http_client
-            .get(url.clone())
-            .header(ACCEPT, "application/json");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            trace!(target: "esplora_rs", "Using auth token");
+    /// Waits for a token from the rate limiter, if one is configured.
+    async fn pace(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    /// Feeds a response status back to the rate limiter, if one is
+    /// configured: `429` tightens it, success loosens it.
+    fn observe(&self, status: reqwest::StatusCode, retry_after: Option<u64>) {
+        if let Some(limiter) = &self.rate_limiter {
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                limiter.on_rate_limited(retry_after);
+            } else if status.is_success() {
+                limiter.on_success();
+            }
+        }
+    }
+
+    /// The rate limiter's current pace in requests per second, or `None`
+    /// without a [`RateLimit`]. Drops below the configured rate after `429`s
+    /// and recovers as requests succeed.
+    pub fn current_rate_limit(&self) -> Option<f64> {
+        self.rate_limiter.as_ref().map(|l| l.current_rate())
+    }
+
+    /// Hit/miss counters and size of the response cache, or `None` without a
+    /// [`CacheConfig`]. Shared by every clone of the client.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(|c| c.stats())
+    }
+
+    /// A clone for following the chain ([`HeaderChain`], [`TxWatcher`],
+    /// [`Self::broadcast_and_confirm`]): its reads of reorg-sensitive
+    /// endpoints (`block-height/:height`, `tx/:txid`, `tx/:txid/status`, …)
+    /// skip the caches, so a reorg deeper than `min_confirmations` can't hide
+    /// behind a stale entry. Its fresh responses still replace cached ones.
+    pub(crate) fn following_chain(&self) -> Client {
+        Client {
+            follows_chain: true,
+            ..self.clone()
+        }
+    }
+
+    /// Tells the memory and disk caches that the chain reorganized back to
+    /// `fork_height`, so they drop what they hold from the orphaned blocks.
+    pub(crate) async fn note_reorg(&self, fork_height: u64) {
+        if let Some(cache) = &self.cache {
+            cache.on_reorg(fork_height);
+        }
+        if let Some(disk_cache) = &self.disk_cache {
+            disk_cache.on_reorg(fork_height).await;
+        }
+    }
+
+    /// Credits remaining and calls per endpoint, from every response this
+    /// client and its clones have received. See [`usage`].
+    pub fn usage_stats(&self) -> UsageStats {
+        self.usage.stats()
+    }
+
+    /// Whether a request may be sent again after it possibly reached a
+    /// server: always when `idempotent`, otherwise only with
+    /// [`RetryPolicy::retry_broadcast`].
+    fn may_resend(&self, idempotent: bool) -> bool {
+        idempotent || self.retry.retry_broadcast
+    }
+
+    /// Runs `op` under the client's [`RetryPolicy`]. `idempotent` operations
+    /// get the full policy; others (broadcasts) only when the policy sets
+    /// [`RetryPolicy::retry_broadcast`]. A failure on the first and only
+    /// attempt is returned unchanged; once a retry has happened, exhaustion is
+    /// reported as [`Error::RetriesExhausted`] carrying every attempt.
+    async fn with_retry<T, F, Fut>(&self, idempotent: bool, mut op: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let max_attempts = if self.may_resend(idempotent) {
+            self.retry.max_attempts.max(1)
+        } else {
+            1
+        };
+        let mut attempts = Vec::new();
+        let mut attempt = 1;
+        loop {
+            let err = match op().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let delay = if attempt < max_attempts && err.is_retryable() {
+                self.retry.delay_for(attempt, err.retry_after())
+            } else {
+                None
+            };
+            if attempts.is_empty() && delay.is_none() {
+                return Err(err);
+            }
+            attempts.push(RetryAttempt {
+                attempt,
+                status: err.status(),
+                error: err.to_string(),
+                delay,
+            });
+            let Some(delay) = delay else {
+                return Err(Error::RetriesExhausted {
+                    attempts,
+                    last: Box::new(err),
+                });
+            };
+            warn!(target: "esplora_rs", "attempt {} failed ({}), retrying in {:?}", attempt, err, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// [`Self::fetch`] for a path without a query, through the memory and
+    /// then the disk cache when configured. Concurrent calls for the same
+    /// path and `accept` share one request (see [`singleflight`]).
+    async fn fetch_path(
+        &self,
+        path: &str,
+        accept: &'static str,
+        label: &str,
+    ) -> Result<Bytes, Error> {
+        let urls = self.backends.urls(path, &[])?;
+        let sensitive = !self.follows_chain;
+        if let Some(body) = self.cache.as_ref().and_then(|c| c.get(path, sensitive)) {
+            trace!(target: "esplora_rs", "{} {} served from cache", label, path);
+            return Ok(body);
+        }
+        if let Some(disk_cache) = &self.disk_cache {
+            if let Some(body) = disk_cache.get(path, sensitive).await {
+                trace!(target: "esplora_rs", "{} {} served from disk cache", label, path);
+                if let Some(cache) = &self.cache {
+                    cache.put(path, &body);
+                }
+                return Ok(body);
+            }
+        }
+        self.in_flight
+            .run(format!("{accept} {path}"), || async {
+                let body = self.fetch(&urls, accept, label).await?;
+                if let Some(cache) = &self.cache {
+                    cache.put(path, &body);
+                }
+                if let Some(disk_cache) = &self.disk_cache {
+                    disk_cache.put(path, &body).await;
+                }
+                Ok(body)
+            })
+            .await
+    }
+
+    /// Fails with [`Error::OfflineMiss`] in offline mode, before anything is
+    /// sent to `url`.
+    fn ensure_online(&self, url: &Url) -> Result<(), Error> {
+        match &self.disk_cache {
+            Some(disk_cache) if disk_cache.offline() => Err(Error::OfflineMiss {
+                url: url.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// The shared GET pipeline behind [`Self::get`], [`Self::get_query`],
+    /// [`Self::get_plain`] and [`Self::get_raw`]: sends with auth, classifies
+    /// non-2xx responses, retries per the policy, and returns the body.
+    /// `urls` holds the request URL resolved against each backend (see
+    /// [`Backends::urls`]); every attempt walks them in failover order.
+    async fn fetch(&self, urls: &[Url], accept: &'static str, label: &str) -> Result<Bytes, Error> {
+        self.ensure_online(&urls[0])?;
+        self.with_retry(true, || {
+            self.backends.run(true, |i| {
+                let url = &urls[i];
+                self.with_auth(i, move |auth| self.fetch_once(url, auth, accept, label))
+            })
+        })
+        .await
+    }
+
+    async fn fetch_once(
+        &self,
+        url: &Url,
+        auth: HeaderMap,
+        accept: &'static str,
+        label: &str,
+    ) -> Result<Bytes, Error> {
+        self.pace().await;
+        debug!(target: "esplora_rs", "{} {}", label, url);
+
+        let mut req = self.http_client.get(url.clone()).header(ACCEPT, accept);
+        if !auth.is_empty() {
+            req = req.headers(auth);
+            trace!(target: "esplora_rs", "Using auth headers");
         }
 
-        let response = req.send().await?;
+        let response = self.read_with_timeout(url, req.send()).await?;
         let status = response.status();
-        debug!(target: "esplora_rs", "GET {} -> {}", url, status);
+        let retry_after = retry_after_secs(&response);
+        self.observe(status, retry_after);
+        self.usage
+            .record(self.backends.locate(url), status, response.headers());
+        debug!(target: "esplora_rs", "{} {} -> {}", label, url, status);
 
         if !status.is_success() {
-            let retry_after = retry_after_secs(&response);
             let body = response.text().await.unwrap_or_default();
-            error!(target: "esplora_rs", "GET {} failed ({}): {}", url, status, body);
-            return Err(Error::from_status(status, &url, body, retry_after));
+            error!(target: "esplora_rs", "{} {} failed ({}): {}", label, url, status, body);
+            return Err(Error::from_status(status, url, body, retry_after));
         }
 
-        Ok(response.json().await?)
+        let bytes = self.read_with_timeout(url, response.bytes()).await?;
+        trace!(target: "esplora_rs", "{} {} returned {} bytes", label, url, bytes.len());
+        Ok(bytes)
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, Error> {
+        let body = self.fetch_path(path, "application/json", "GET").await?;
+        Ok(serde_json::from_slice(&body)?)
     }
 
     /// Like [`Self::get`], but attaches URL-encoded query parameters. The `url`
@@ -188,135 +588,82 @@ impl Client {
         path: &str,
         params: Vec<(String, String)>,
     ) -> Result<T, Error> {
-        // Build the URL (consuming the owned `params`) *before* any await, and
+        // Build the URLs (consuming the owned `params`) *before* any await, and
         // take `params` by value. Holding any borrowed query slice
         // (`&[(&str, &str)]`) alive across an await point trips the "Send is not
         // general enough" HRTB bound when this future is driven from an async
         // request handler; owned `Vec<(String, String)>` sidesteps it.
-        let mut url = self.base_url.join(path)?;
-        url.query_pairs_mut()
-            .extend_pairs(params.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        let urls = self.backends.urls(path, &params)?;
         drop(params);
-        let token = self.auth.get_token().await?;
-        debug!(target: "esplora_rs", "GET {}", url);
-
-        let mut req = self
-            .http_client
-            .get(url.clone())
-            .header(ACCEPT, "application/json");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            trace!(target: "esplora_rs", "Using auth token");
-        }
-
-        let response = req.send().await?;
-        let status = response.status();
-        debug!(target: "esplora_rs", "GET {} -> {}", url, status);
-
-        if !status.is_success() {
-            let retry_after = retry_after_secs(&response);
-            let body = response.text().await.unwrap_or_default();
-            error!(target: "esplora_rs", "GET {} failed ({}): {}", url, status, body);
-            return Err(Error::from_status(status, &url, body, retry_after));
-        }
-
-        Ok(response.json().await?)
+        let body = self.fetch(&urls, "application/json", "GET").await?;
+        Ok(serde_json::from_slice(&body)?)
     }
 
-    /// Generic POST returning a JSON body. Unused since `broadcast_tx` reads
-    /// the plain-text txid directly; kept for future POST-JSON endpoints.
-    #[allow(dead_code)]
+    /// POSTs a JSON `body` and decodes a JSON response, with the same auth,
+    /// pacing, failover and error classification as the GETs. A POST that
+    /// submits transactions isn't `idempotent`: like `broadcast_tx`, it is
+    /// only retried when the policy sets [`RetryPolicy::retry_broadcast`].
     async fn post<T: serde::de::DeserializeOwned>(
         &self,
         path: &str,
         body: String,
+        idempotent: bool,
     ) -> Result<T, Error> {
-        let token = self.auth.get_token().await?;
-        let url = self.base_url.join(path)?;
+        let urls = self.backends.urls(path, &[])?;
+        self.ensure_online(&urls[0])?;
+        let bytes = self
+            .with_retry(idempotent, || {
+                self.backends.run(self.may_resend(idempotent), |i| {
+                    let (url, body) = (&urls[i], &body);
+                    self.with_auth(i, move |auth| self.post_once(url, auth, body.clone()))
+                })
+            })
+            .await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    async fn post_once(&self, url: &Url, auth: HeaderMap, body: String) -> Result<Bytes, Error> {
+        self.pace().await;
         debug!(target: "esplora_rs", "POST {} (body_len={})", url, body.len());
         trace!(target: "esplora_rs", "POST body: {}", &body[..body.len().min(200)]);
 
         let mut req = self
             .http_client
             .post(url.clone())
-            .header(ACCEPT, "application/json");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            trace!(target: "esplora_rs", "Using auth token");
+            .header(ACCEPT, "application/json")
+            .header(reqwest::header::CONTENT_TYPE, "application/json");
+        if !auth.is_empty() {
+            req = req.headers(auth);
+            trace!(target: "esplora_rs", "Using auth headers");
         }
 
-        let response = req.body(body).send().await?;
+        let response = self.read_with_timeout(url, req.body(body).send()).await?;
         let status = response.status();
+        let retry_after = retry_after_secs(&response);
+        self.observe(status, retry_after);
+        self.usage
+            .record(self.backends.locate(url), status, response.headers());
         debug!(target: "esplora_rs", "POST {} -> {}", url, status);
 
         if !status.is_success() {
-            let retry_after = retry_after_secs(&response);
             let resp_body = response.text().await.unwrap_or_default();
             error!(target: "esplora_rs", "POST {} failed ({}): {}", url, status, resp_body);
-            return Err(Error::from_status(status, &url, resp_body, retry_after));
+            return Err(Error::from_status(status, url, resp_body, retry_after));
         }
 
-        Ok(response.json().await?)
+        self.read_with_timeout(url, response.bytes()).await
     }
 
     async fn get_plain(&self, path: &str) -> Result<String, Error> {
-        let token = self.auth.get_token().await?;
-        let url = self.base_url.join(path)?;
-        debug!(target: "esplora_rs", "GET (plain) {}", url);
-
-        let mut req = self
-            .http_client
-            .get(url.clone())
-            .header(ACCEPT, "text/plain");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            trace!(target: "esplora_rs", "Using auth token");
-        }
-
-        let response = req.send().await?;
-        let status = response.status();
-        let retry_after = retry_after_secs(&response);
-        let body = response.text().await?;
-
-        debug!(target: "esplora_rs", "GET (plain) {} -> {} (len={})", url, status, body.len());
-
-        if !status.is_success() {
-            error!(target: "esplora_rs", "GET (plain) {} failed ({}): {}", url, status, body);
-            return Err(Error::from_status(status, &url, body, retry_after));
-        }
-
+        let body = self.fetch_path(path, "text/plain", "GET (plain)").await?;
+        let body = String::from_utf8_lossy(&body).into_owned();
         trace!(target: "esplora_rs", "GET (plain) response: {}", &body[..body.len().min(200)]);
         Ok(body)
     }
 
     async fn get_raw(&self, path: &str) -> Result<Bytes, Error> {
-        let token = self.auth.get_token().await?;
-        let url = self.base_url.join(path)?;
-        debug!(target: "esplora_rs", "GET (raw) {}", url);
-
-        let mut req = self
-            .http_client
-            .get(url.clone())
-            .header(ACCEPT, "application/octet-stream");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            trace!(target: "esplora_rs", "Using auth token");
-        }
-
-        let response = req.send().await?;
-        let status = response.status();
-        debug!(target: "esplora_rs", "GET (raw) {} -> {}", url, status);
-
-        if !status.is_success() {
-            let retry_after = retry_after_secs(&response);
-            let body = response.text().await.unwrap_or_default();
-            error!(target: "esplora_rs", "GET (raw) {} failed ({}): {}", url, status, body);
-            return Err(Error::from_status(status, &url, body, retry_after));
-        }
-
-        let bytes = response.bytes().await?;
-        debug!(target: "esplora_rs", "GET (raw) {} returned {} bytes", url, bytes.len());
-        Ok(bytes)
+        self.fetch_path(path, "application/octet-stream", "GET (raw)")
+            .await
     }
 
     // Blocks
@@ -329,7 +676,8 @@ impl Client {
         self.get(&format!("block/{}", hash)).await
     }
 
-    /// Gets the hex-encoded block header by its hash.
+    /// Gets the hex-encoded block header by its hash. Decode it with
+    /// [`BlockHeader::from_hex`].
     ///
     /// # Errors
     /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
@@ -483,6 +831,18 @@ impl Client {
             .await
     }
 
+    /// Gets the merkle inclusion proof for a confirmed transaction. Verify it
+    /// locally with [`MerkleProof::verify_header`] or
+    /// [`MerkleProof::verify_block`].
+    ///
+    /// # Errors
+    /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
+    /// status (e.g. `404` for an unconfirmed transaction), or the response
+    /// body cannot be decoded.
+    pub async fn get_tx_merkle_proof(&self, txid: &str) -> Result<MerkleProof, Error> {
+        self.get(&format!("tx/{}/merkle-proof", txid)).await
+    }
+
     /// Gets the spending status of a transaction output.
     ///
     /// # Errors
@@ -504,16 +864,36 @@ impl Client {
     /// Broadcasts a transaction to the network.
     ///
     /// Returns the transaction ID on success, or an error with the rejection reason.
+    /// Not retried, nor sent to a fallback backend after a timeout or `5xx`,
+    /// unless the client's [`RetryPolicy`] sets
+    /// [`RetryPolicy::retry_broadcast`].
     ///
     /// # Errors
     /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
-    /// status, or the response body cannot be decoded.
+    /// status, or the response body cannot be decoded. A node rejection is an
+    /// [`Error::Http`] `400`; [`Error::broadcast_rejection`] says why.
     pub async fn broadcast_tx(&self, tx_hex: &str) -> Result<String, Error> {
+        let urls = self.backends.urls("tx", &[])?;
+        self.ensure_online(&urls[0])?;
+        self.with_retry(false, || {
+            self.backends.run(self.may_resend(false), |i| {
+                let url = &urls[i];
+                self.with_auth(i, move |auth| self.broadcast_tx_once(url, auth, tx_hex))
+            })
+        })
+        .await
+    }
+
+    async fn broadcast_tx_once(
+        &self,
+        url: &Url,
+        auth: HeaderMap,
+        tx_hex: &str,
+    ) -> Result<String, Error> {
         info!(target: "esplora_rs", "broadcast_tx: Starting broadcast of {} byte tx", tx_hex.len() / 2);
         debug!(target: "esplora_rs", "broadcast_tx: tx_hex first 100 chars: {}", &tx_hex[..tx_hex.len().min(100)]);
 
-        let token = self.auth.get_token().await?;
-        let url = self.base_url.join("tx")?;
+        self.pace().await;
 
         info!(target: "esplora_rs", "broadcast_tx: POST {}", url);
         debug!(target: "esplora_rs", "broadcast_tx: Headers - Accept: text/plain, Content-Type: text/plain");
@@ -523,36 +903,42 @@ impl Client {
             .post(url.clone())
             .header(ACCEPT, "text/plain")
             .header(reqwest::header::CONTENT_TYPE, "text/plain");
-        if let Some(token) = token {
-            req = req.header(AUTHORIZATION, format!("Bearer {}", token));
-            debug!(target: "esplora_rs", "broadcast_tx: Using auth token");
+        if !auth.is_empty() {
+            req = req.headers(auth);
+            debug!(target: "esplora_rs", "broadcast_tx: Using auth headers");
         }
 
         debug!(target: "esplora_rs", "broadcast_tx: Sending request...");
-        let response = match req.body(tx_hex.to_string()).send().await {
+        let response = match self
+            .read_with_timeout(url, req.body(tx_hex.to_string()).send())
+            .await
+        {
             Ok(resp) => {
                 debug!(target: "esplora_rs", "broadcast_tx: Request sent successfully");
                 resp
             }
             Err(e) => {
                 error!(target: "esplora_rs", "broadcast_tx: Request failed to send: {}", e);
-                return Err(e.into());
+                return Err(e);
             }
         };
 
         let status = response.status();
         let retry_after = retry_after_secs(&response);
+        self.observe(status, retry_after);
+        self.usage
+            .record(self.backends.locate(url), status, response.headers());
         info!(target: "esplora_rs", "broadcast_tx: Response status: {} {}", status.as_u16(), status.canonical_reason().unwrap_or(""));
 
         debug!(target: "esplora_rs", "broadcast_tx: Reading response body...");
-        let body = match response.text().await {
+        let body = match self.read_with_timeout(url, response.text()).await {
             Ok(text) => {
                 debug!(target: "esplora_rs", "broadcast_tx: Response body ({} bytes): {}", text.len(), text.trim());
                 text
             }
             Err(e) => {
                 error!(target: "esplora_rs", "broadcast_tx: Failed to read response body: {}", e);
-                return Err(e.into());
+                return Err(e);
             }
         };
 
@@ -569,13 +955,69 @@ impl Client {
             );
             Err(Error::from_status(
                 status,
-                &url,
+                url,
                 body.trim().to_string(),
                 retry_after,
             ))
         }
     }
 
+    /// Broadcasts a package of related transactions together (`POST
+    /// txs/package`, bitcoind's `submitpackage`), so a child can pay for a
+    /// parent that couldn't be relayed alone — a zero-fee parent for CPFP, or
+    /// a TRUC (v3) parent and child. List parents before children.
+    ///
+    /// Only newer Esplora and mempool.space deployments have this endpoint.
+    /// Not retried, nor sent to a fallback backend after a timeout or `5xx`,
+    /// unless the client's [`RetryPolicy`] sets
+    /// [`RetryPolicy::retry_broadcast`].
+    ///
+    /// # Errors
+    /// Returns [`Error::Unsupported`] if the server doesn't have the
+    /// endpoint, otherwise [`Error`] if the request fails, the endpoint
+    /// returns a non-2xx status, or the response body cannot be decoded. A
+    /// package the node refuses outright is an [`Error::Http`] `400` (see
+    /// [`Error::broadcast_rejection`]); per-transaction failures are
+    /// reported in [`PackageResult::tx_results`] instead.
+    pub async fn broadcast_package<S: AsRef<str>>(
+        &self,
+        tx_hexes: &[S],
+    ) -> Result<PackageResult, Error> {
+        let txs: Vec<&str> = tx_hexes.iter().map(AsRef::as_ref).collect();
+        let body = serde_json::to_string(&txs)?;
+        info!(target: "esplora_rs", "broadcast_package: {} transaction(s)", txs.len());
+        self.post("txs/package", body, false)
+            .await
+            .map_err(|err| err.into_unsupported("POST txs/package"))
+    }
+
+    /// Checks whether transactions would be accepted into the mempool
+    /// without broadcasting them (`POST txs/test`, bitcoind's
+    /// `testmempoolaccept`), returning one result per transaction, in order.
+    /// Several transactions are tested as a package: parents before
+    /// children.
+    ///
+    /// Use [`MempoolAcceptResult::rejection`] to categorize a refusal the
+    /// same way as a failed [`Self::broadcast_tx`].
+    ///
+    /// Nothing is broadcast, so it is retried and failed over like a GET.
+    ///
+    /// # Errors
+    /// Returns [`Error::Unsupported`] if the server doesn't have the
+    /// endpoint (mempool.space-compatible backends do), otherwise [`Error`]
+    /// if the request fails, the endpoint returns a non-2xx status, or the
+    /// response body cannot be decoded.
+    pub async fn test_mempool_accept<S: AsRef<str>>(
+        &self,
+        tx_hexes: &[S],
+    ) -> Result<Vec<MempoolAcceptResult>, Error> {
+        let txs: Vec<&str> = tx_hexes.iter().map(AsRef::as_ref).collect();
+        let body = serde_json::to_string(&txs)?;
+        self.post("txs/test", body, true)
+            .await
+            .map_err(|err| err.into_unsupported("POST txs/test"))
+    }
+
     // Addresses
     /// Gets information about an address.
     ///
@@ -639,6 +1081,52 @@ impl Client {
         Ok(utxos)
     }
 
+    /// Gets a list of transactions for a scripthash (see [`scripthash`]).
+    ///
+    /// # Errors
+    /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
+    /// status, or the response body cannot be decoded.
+    pub async fn get_scripthash_txs(&self, hash: &str) -> Result<Vec<Transaction>, Error> {
+        self.get(&format!("scripthash/{}/txs", hash)).await
+    }
+
+    /// Gets a list of confirmed transactions for a scripthash, starting after
+    /// a specific transaction.
+    ///
+    /// # Errors
+    /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
+    /// status, or the response body cannot be decoded.
+    pub async fn get_scripthash_txs_chain(
+        &self,
+        hash: &str,
+        last_seen_txid: Option<&str>,
+    ) -> Result<Vec<Transaction>, Error> {
+        let path = if let Some(txid) = last_seen_txid {
+            format!("scripthash/{}/txs/chain/{}", hash, txid)
+        } else {
+            format!("scripthash/{}/txs/chain", hash)
+        };
+        self.get(&path).await
+    }
+
+    /// Gets a list of unconfirmed transactions for a scripthash.
+    ///
+    /// # Errors
+    /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
+    /// status, or the response body cannot be decoded.
+    pub async fn get_scripthash_mempool_txs(&self, hash: &str) -> Result<Vec<Transaction>, Error> {
+        self.get(&format!("scripthash/{}/txs/mempool", hash)).await
+    }
+
+    /// Gets a list of unspent transaction outputs for a scripthash.
+    ///
+    /// # Errors
+    /// Returns [`Error`] if the request fails, the endpoint returns a non-2xx
+    /// status, or the response body cannot be decoded.
+    pub async fn get_scripthash_utxos(&self, hash: &str) -> Result<Vec<Utxo>, Error> {
+        self.get(&format!("scripthash/{}/utxo", hash)).await
+    }
+
     /// Searches for addresses with a given prefix.
     ///
     /// # Errors
@@ -940,52 +1428,298 @@ mod tests {
         assert_eq!(result.unwrap(), 600000);
     }
 
-    // ── E1: structured HTTP error classification ────────────────────────────
+    // ── Explicit credentials / auth mode from the environment ───────────────
 
-    fn test_client(server: &MockServer) -> Client {
-        let token_url = Url::parse(&server.url("/token")).unwrap();
-        Client::from_parts(
+    #[tokio::test]
+    async fn test_with_credentials_and_token_url() {
+        let server = MockServer::start();
+        mock_auth_server(&server);
+        let tip = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("Authorization", "Bearer test_token");
+            then.status(200).body("812345");
+        });
+
+        let client = Client::with_credentials_and_token_url(
             &server.base_url(),
-            token_url,
-            "test_id".to_string(),
-            "test_secret".to_string(),
+            "test_id",
+            "test_secret",
+            &server.url("/token"),
         )
-        .unwrap()
+        .unwrap();
+        assert_eq!(client.get_tip_height().await.unwrap(), 812_345);
+        tip.assert();
+
+        assert!(matches!(
+            Client::with_credentials_and_token_url(&server.base_url(), "id", "secret", "not a url"),
+            Err(Error::Url(_))
+        ));
     }
 
     #[tokio::test]
-    async fn test_non_2xx_maps_to_http_with_status_and_body() {
+    async fn test_token_providers_set_request_headers() {
         let server = MockServer::start();
-        mock_auth_server(&server);
-        let api_mock = server.mock(|when, then| {
-            when.method(GET).path("/address/tb1qbad");
-            then.status(404).body("not found");
+        let keyed = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("X-API-Key", "k3y");
+            then.status(200).body("1");
+        });
+        let basic = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("Authorization", "Basic dXNlcjpwYXNz");
+            then.status(200).body("00ff");
         });
 
-        let err = test_client(&server)
-            .get_address_info("tb1qbad")
-            .await
-            .unwrap_err();
-        api_mock.assert();
-        match err {
-            Error::Http { status, body, url } => {
-                assert_eq!(status, 404);
-                assert!(body.contains("not found"), "body: {body}");
-                assert!(url.ends_with("/address/tb1qbad"), "url: {url}");
-            }
-            other => panic!("expected Error::Http, got {other:?}"),
+        let client = Client::builder(&server.base_url())
+            .auth(StaticHeader::new("X-API-Key", "k3y").unwrap())
+            .build()
+            .unwrap();
+        client.get_tip_height().await.unwrap();
+        keyed.assert();
+
+        let shared: Arc<dyn TokenProvider> = Arc::new(StaticHeader::basic("user", "pass").unwrap());
+        let client = Client::builder(&server.base_url())
+            .auth(shared)
+            .build()
+            .unwrap();
+        client.get_tip_hash().await.unwrap();
+        basic.assert();
+    }
+
+    /// Mints `Bearer minted-<n>` for the n-th call.
+    #[derive(Debug, Default)]
+    struct Counting(std::sync::atomic::AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl TokenProvider for Counting {
+        async fn auth_headers(&self) -> Result<reqwest::header::HeaderMap, Error> {
+            let n = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let value = format!("Bearer minted-{n}").parse().unwrap();
+            Ok([(reqwest::header::AUTHORIZATION, value)]
+                .into_iter()
+                .collect())
         }
     }
 
     #[tokio::test]
-    async fn test_429_maps_to_rate_limited_with_retry_after() {
+    async fn test_custom_token_provider_is_consulted_per_attempt() {
         let server = MockServer::start();
-        mock_auth_server(&server);
-        let api_mock = server.mock(|when, then| {
-            when.method(GET).path("/blocks/tip/height");
-            then.status(429)
-                .header("Retry-After", "7")
-                .body("slow down");
+        let first = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("Authorization", "Bearer minted-0");
+            then.status(503);
+        });
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("Authorization", "Bearer minted-1");
+            then.status(200).body("7");
+        });
+        let provider = Arc::new(Counting::default());
+        let client = Client::builder(&server.base_url())
+            .auth(provider.clone())
+            .retry(fast_retry(2))
+            .build()
+            .unwrap();
+        assert_eq!(client.get_tip_height().await.unwrap(), 7);
+        first.assert();
+        second.assert();
+    }
+
+    #[tokio::test]
+    async fn test_rejected_credentials_are_refreshed_once() {
+        let server = MockServer::start();
+        let revoked = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("Authorization", "Bearer minted-0");
+            then.status(401).body("token revoked");
+        });
+        let fresh = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("Authorization", "Bearer minted-1");
+            then.status(200).body("7");
+        });
+        let client = Client::builder(&server.base_url())
+            .auth(Counting::default())
+            .build()
+            .unwrap();
+        assert_eq!(client.get_tip_height().await.unwrap(), 7);
+        revoked.assert();
+        fresh.assert();
+    }
+
+    #[tokio::test]
+    async fn test_forbidden_is_retried_only_for_expired_tokens() {
+        let server = MockServer::start();
+        let mut expired = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("Authorization", "Bearer minted-0");
+            then.status(403)
+                .body(r#"{"error":"invalid_token","error_description":"Token expired"}"#);
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("Authorization", "Bearer minted-1");
+            then.status(200).body("00ff");
+        });
+        let client = Client::builder(&server.base_url())
+            .auth(Counting::default())
+            .build()
+            .unwrap();
+        assert_eq!(client.get_tip_hash().await.unwrap(), "00ff");
+        expired.assert();
+        expired.delete();
+
+        let forbidden = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(403).body("plan does not include this endpoint");
+        });
+        let err = client.get_tip_height().await.unwrap_err();
+        assert_eq!(err.status(), Some(403));
+        assert!(!err.is_auth_rejected());
+        forbidden.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_oauth_token_rejected_twice_gives_up() {
+        let server = MockServer::start();
+        let token = server.mock(|when, then| {
+            when.method(POST).path("/token");
+            then.status(200)
+                .body(r#"{"access_token": "test_token", "expires_in": 300}"#);
+        });
+        let api = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(401);
+        });
+        let err = test_client(&server).get_tip_height().await.unwrap_err();
+        assert_eq!(err.status(), Some(401));
+        token.assert_hits(2);
+        api.assert_hits(2);
+    }
+
+    /// `Client::from_env` over `vars` instead of the process environment;
+    /// `Ok(true)` for an enterprise client.
+    fn from_vars(vars: &[(&str, &str)]) -> Result<bool, Error> {
+        let vars: std::collections::HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let client =
+            Client::from_lookup("https://example.com/api", |name| vars.get(name).cloned())?;
+        Ok(client.backends.get(0).auth.is_some())
+    }
+
+    const CREDS: [(&str, &str); 2] = [
+        ("ESPLORA_CLIENT_ID", "id"),
+        ("ESPLORA_CLIENT_SECRET", "secret"),
+    ];
+
+    #[test]
+    fn test_from_env_auto_mode() {
+        assert!(!from_vars(&[]).unwrap());
+        assert!(from_vars(&CREDS).unwrap());
+        assert!(from_vars(&[("ESPLORA_AUTH_MODE", "auto"), CREDS[0], CREDS[1]]).unwrap());
+        // An empty mode is the default; half the credentials is none.
+        assert!(from_vars(&[("ESPLORA_AUTH_MODE", ""), CREDS[0], CREDS[1]]).unwrap());
+        assert!(!from_vars(&[CREDS[0]]).unwrap());
+    }
+
+    #[test]
+    fn test_from_env_empty_credentials_count_as_absent() {
+        let empty_secret = [CREDS[0], ("ESPLORA_CLIENT_SECRET", "")];
+        assert!(!from_vars(&empty_secret).unwrap());
+        let err = from_vars(&[
+            ("ESPLORA_AUTH_MODE", "enterprise"),
+            empty_secret[0],
+            empty_secret[1],
+        ])
+        .unwrap_err();
+        assert!(
+            matches!(err, Error::EnvVar(ref v) if v == "ESPLORA_CLIENT_SECRET"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_from_env_public_mode_ignores_credentials() {
+        assert!(!from_vars(&[("ESPLORA_AUTH_MODE", "public"), CREDS[0], CREDS[1]]).unwrap());
+        assert!(!from_vars(&[("ESPLORA_AUTH_MODE", "public")]).unwrap());
+    }
+
+    #[test]
+    fn test_from_env_enterprise_mode_fails_closed() {
+        assert!(from_vars(&[("ESPLORA_AUTH_MODE", "enterprise"), CREDS[0], CREDS[1]]).unwrap());
+        let err = from_vars(&[("ESPLORA_AUTH_MODE", "Enterprise")]).unwrap_err();
+        assert!(
+            matches!(err, Error::EnvVar(ref v) if v == "ESPLORA_CLIENT_ID"),
+            "{err:?}"
+        );
+    }
+
+    #[test]
+    fn test_from_env_rejects_unknown_mode() {
+        let err = from_vars(&[("ESPLORA_AUTH_MODE", "private"), CREDS[0], CREDS[1]]).unwrap_err();
+        assert!(
+            matches!(err, Error::Config(ref m) if m.contains("\"private\"")),
+            "{err:?}"
+        );
+    }
+
+    // ── E1: structured HTTP error classification ────────────────────────────
+
+    fn test_client(server: &MockServer) -> Client {
+        let token_url = Url::parse(&server.url("/token")).unwrap();
+        Client::from_parts(
+            &server.base_url(),
+            token_url,
+            "test_id".to_string(),
+            "test_secret".to_string(),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_non_2xx_maps_to_http_with_status_and_body() {
+        let server = MockServer::start();
+        mock_auth_server(&server);
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/address/tb1qbad");
+            then.status(404).body("not found");
+        });
+
+        let err = test_client(&server)
+            .get_address_info("tb1qbad")
+            .await
+            .unwrap_err();
+        api_mock.assert();
+        match err {
+            Error::Http { status, body, url } => {
+                assert_eq!(status, 404);
+                assert!(body.contains("not found"), "body: {body}");
+                assert!(url.ends_with("/address/tb1qbad"), "url: {url}");
+            }
+            other => panic!("expected Error::Http, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_429_maps_to_rate_limited_with_retry_after() {
+        let server = MockServer::start();
+        mock_auth_server(&server);
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(429)
+                .header("Retry-After", "7")
+                .body("slow down");
         });
 
         let err = test_client(&server).get_tip_height().await.unwrap_err();
@@ -1010,6 +1744,8 @@ mod tests {
             .await
             .unwrap_err();
         api_mock.assert();
+        let rejection = err.broadcast_rejection().expect("a 400 from broadcast");
+        assert_eq!(rejection.reason, BroadcastRejection::MissingInputs);
         match err {
             Error::Http { status, body, .. } => {
                 assert_eq!(status, 400);
@@ -1049,6 +1785,2363 @@ mod tests {
         assert!(limited.to_string().contains("retry_after"));
     }
 
+    // ── E2/E5/E9: ClientBuilder ─────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_builder_sends_user_agent_and_default_headers() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/height")
+                .header("user-agent", "my-wallet/1.0")
+                .header("x-api-key", "k1");
+            then.status(200).body("600000");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .user_agent("my-wallet/1.0")
+            .default_header("X-Api-Key", "k1")
+            .build()
+            .unwrap();
+        let height = client.get_tip_height().await.unwrap();
+
+        api_mock.assert();
+        assert_eq!(height, 600000);
+    }
+
+    #[tokio::test]
+    async fn test_default_user_agent_is_crate_version() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("user-agent", builder::DEFAULT_USER_AGENT);
+            then.status(200).body("00ff");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        assert_eq!(client.get_tip_hash().await.unwrap(), "00ff");
+        api_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_maps_to_timeout_error() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(200)
+                .body("600000")
+                .delay(Duration::from_millis(500));
+        });
+
+        let client = Client::builder(&server.base_url())
+            .read_timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let err = client.get_tip_height().await.unwrap_err();
+        match err {
+            Error::Timeout { url, timeout } => {
+                assert!(url.ends_with("/blocks/tip/height"), "url: {url}");
+                assert_eq!(timeout, Duration::from_millis(50));
+            }
+            other => panic!("expected Error::Timeout, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_total_timeout_maps_to_reqwest_timeout() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(200)
+                .body("600000")
+                .delay(Duration::from_millis(500));
+        });
+
+        let client = Client::builder(&server.base_url())
+            .timeout(Duration::from_millis(50))
+            .build()
+            .unwrap();
+        match client.get_tip_height().await.unwrap_err() {
+            Error::Reqwest(e) => assert!(e.is_timeout(), "{e}"),
+            other => panic!("expected a reqwest timeout, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_builder_uses_injected_http_client() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("user-agent", "injected/0.1");
+            then.status(200).body("00ff");
+        });
+
+        let http_client = ReqwestClient::builder()
+            .user_agent("injected/0.1")
+            .build()
+            .unwrap();
+        let client = Client::builder(&server.base_url())
+            .user_agent("ignored/1.0")
+            .http_client(http_client)
+            .build()
+            .unwrap();
+        assert_eq!(client.get_tip_hash().await.unwrap(), "00ff");
+        api_mock.assert();
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_header() {
+        let err = Client::builder("https://example.com/api")
+            .default_header("bad header", "v")
+            .build()
+            .unwrap_err();
+        assert!(matches!(err, Error::Config(_)), "got {err:?}");
+    }
+
+    #[test]
+    fn test_builder_rejects_unusable_rate_limit() {
+        for rate in [0.0, -2.0, f64::NAN] {
+            let err = Client::builder("https://example.com/api")
+                .rate_limit(RateLimit::new(rate, 5))
+                .build()
+                .unwrap_err();
+            assert!(matches!(err, Error::Config(_)), "{rate}: got {err:?}");
+        }
+    }
+
+    // ── E3: retry / backoff ─────────────────────────────────────────────────
+
+    /// Polls until `mock` has been hit at least `n` times.
+    async fn wait_for_hits(mock: &httpmock::Mock<'_>, n: usize) {
+        for _ in 0..400 {
+            if mock.hits_async().await >= n {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("mock was not hit {n} times");
+    }
+
+    fn fast_retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy::with_max_attempts(max_attempts)
+            .backoff(Duration::from_millis(10), Duration::from_secs(1))
+            .jitter(false)
+    }
+
+    #[tokio::test]
+    async fn test_retry_recovers_after_503() {
+        let server = MockServer::start();
+        let failing = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(503).body("overloaded");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .retry(
+                RetryPolicy::with_max_attempts(3)
+                    .backoff(Duration::from_millis(300), Duration::from_secs(1))
+                    .jitter(false),
+            )
+            .build()
+            .unwrap();
+        let (result, ()) = tokio::join!(client.get_tip_height(), async {
+            wait_for_hits(&failing, 1).await;
+            failing.delete_async().await;
+            server.mock(|when, then| {
+                when.method(GET).path("/blocks/tip/height");
+                then.status(200).body("600000");
+            });
+        });
+
+        assert_eq!(result.unwrap(), 600000);
+    }
+
+    #[tokio::test]
+    async fn test_retry_exhaustion_records_every_attempt() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/hash");
+            then.status(503).body("overloaded");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .retry(fast_retry(3))
+            .build()
+            .unwrap();
+        let err = client.get_tip_hash().await.unwrap_err();
+
+        api_mock.assert_hits(3);
+        match err {
+            Error::RetriesExhausted { attempts, last } => {
+                assert_eq!(attempts.len(), 3);
+                assert!(attempts.iter().all(|a| a.status == Some(503)));
+                assert_eq!(attempts[0].delay, Some(Duration::from_millis(10)));
+                assert_eq!(attempts[1].delay, Some(Duration::from_millis(20)));
+                assert_eq!(attempts[2].delay, None);
+                assert!(matches!(*last, Error::Http { status: 503, .. }));
+            }
+            other => panic!("expected Error::RetriesExhausted, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_skips_non_retryable_status() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/tx/missing");
+            then.status(404).body("not found");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .retry(fast_retry(3))
+            .build()
+            .unwrap();
+        let err = client.get_tx("missing").await.unwrap_err();
+
+        api_mock.assert_hits(1);
+        assert!(
+            matches!(err, Error::Http { status: 404, .. }),
+            "got {err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_when_retry_after_exceeds_max_delay() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(429)
+                .header("Retry-After", "120")
+                .body("slow down");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .retry(fast_retry(3))
+            .build()
+            .unwrap();
+        let err = client.get_tip_height().await.unwrap_err();
+
+        api_mock.assert_hits(1);
+        assert_eq!(err.retry_after(), Some(120));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_is_not_retried_unless_opted_in() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(503).body("overloaded");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .retry(fast_retry(3))
+            .build()
+            .unwrap();
+        let err = client.broadcast_tx("deadbeef").await.unwrap_err();
+        api_mock.assert_hits(1);
+        assert!(
+            matches!(err, Error::Http { status: 503, .. }),
+            "got {err:?}"
+        );
+
+        let client = Client::builder(&server.base_url())
+            .retry(fast_retry(2).retry_broadcast(true))
+            .build()
+            .unwrap();
+        let err = client.broadcast_tx("deadbeef").await.unwrap_err();
+        api_mock.assert_hits(3);
+        assert!(matches!(err, Error::RetriesExhausted { .. }), "got {err:?}");
+    }
+
+    #[test]
+    fn test_parse_retry_after_forms() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(parse_retry_after(" 42 ", now), Some(42));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:30 GMT", now),
+            Some(30)
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(0)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    // ── Multi-backend failover ──────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_failover_to_second_backend_on_503() {
+        let primary = MockServer::start();
+        let backup = MockServer::start();
+        let primary_mock = primary.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(503).body("overloaded");
+        });
+        let backup_mock = backup.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(200).body("600000");
+        });
+
+        let client = Client::builder(&primary.base_url())
+            .fallback(&backup.base_url(), Auth::new_public())
+            .build()
+            .unwrap();
+        let (height, served) = failover::served_by(client.get_tip_height()).await;
+        assert_eq!(height.unwrap(), 600000);
+        assert_eq!(served, vec![format!("{}/", backup.base_url())]);
+
+        // The primary is now cooling down, so the next call goes straight to
+        // the backup.
+        let status = client.backend_status();
+        assert!(status[0].cooldown_remaining.is_some());
+        assert!(status[1].cooldown_remaining.is_none());
+        client.get_tip_height().await.unwrap();
+        primary_mock.assert_hits(1);
+        backup_mock.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_does_not_fail_over_after_timeout() {
+        let primary = MockServer::start();
+        let backup = MockServer::start();
+        primary.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(200)
+                .delay(Duration::from_millis(500))
+                .body("t1");
+        });
+        let backup_mock = backup.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(400).body("txn-already-known");
+        });
+
+        let client = Client::builder(&primary.base_url())
+            .fallback(&backup.base_url(), Auth::new_public())
+            .read_timeout(Duration::from_millis(100))
+            .build()
+            .unwrap();
+        let err = client.broadcast_tx("00").await.unwrap_err();
+        assert!(matches!(err, Error::Timeout { .. }), "{err:?}");
+        backup_mock.assert_hits(0);
+
+        // A connect error means nothing was sent, so that does fail over.
+        let client = Client::builder("http://127.0.0.1:1/")
+            .fallback(&backup.base_url(), Auth::new_public())
+            .build()
+            .unwrap();
+        assert!(client.broadcast_tx("00").await.is_err());
+        backup_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_failover_uses_each_backends_auth() {
+        let backup = MockServer::start();
+        mock_auth_server(&backup);
+        let api_mock = backup.mock(|when, then| {
+            when.method(GET)
+                .path("/blocks/tip/hash")
+                .header("Authorization", "Bearer test_token");
+            then.status(200).body("00ff");
+        });
+
+        // Nothing listens on port 1: the primary fails with a connect error.
+        let token_url = Url::parse(&backup.url("/token")).unwrap();
+        let client = Client::builder("http://127.0.0.1:1/api")
+            .fallback(
+                &backup.base_url(),
+                Auth::new("test_id".to_string(), "test_secret".to_string(), token_url),
+            )
+            .build()
+            .unwrap();
+        assert_eq!(client.get_tip_hash().await.unwrap(), "00ff");
+        api_mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_failover_skips_non_retryable_errors() {
+        let primary = MockServer::start();
+        let backup = MockServer::start();
+        primary.mock(|when, then| {
+            when.method(GET).path("/tx/missing");
+            then.status(404).body("not found");
+        });
+        let backup_mock = backup.mock(|when, then| {
+            when.method(GET).path("/tx/missing");
+            then.status(200).body("{}");
+        });
+
+        let client = Client::builder(&primary.base_url())
+            .fallback(&backup.base_url(), Auth::new_public())
+            .build()
+            .unwrap();
+        let err = client.get_tx("missing").await.unwrap_err();
+        assert!(
+            matches!(err, Error::Http { status: 404, .. }),
+            "got {err:?}"
+        );
+        backup_mock.assert_hits(0);
+        assert!(client.backend_status()[0].cooldown_remaining.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failover_returns_last_error_when_all_backends_fail() {
+        let primary = MockServer::start();
+        let backup = MockServer::start();
+        for server in [&primary, &backup] {
+            server.mock(|when, then| {
+                when.method(GET).path("/blocks/tip/hash");
+                then.status(502).body("bad gateway");
+            });
+        }
+
+        let client = Client::builder(&primary.base_url())
+            .fallback(&backup.base_url(), Auth::new_public())
+            .build()
+            .unwrap();
+        let err = client.get_tip_hash().await.unwrap_err();
+        match err {
+            Error::Http { status, url, .. } => {
+                assert_eq!(status, 502);
+                assert!(url.starts_with(&backup.base_url()), "url: {url}");
+            }
+            other => panic!("expected Error::Http, got {other:?}"),
+        }
+    }
+
+    // ── Client-side rate limiting ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_rate_limit_paces_requests_across_clones() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/hash");
+            then.status(200).body("00ff");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .rate_limit(RateLimit::new(20.0, 2))
+            .build()
+            .unwrap();
+        let clone = client.clone();
+        let start = std::time::Instant::now();
+        for c in [&client, &clone, &client, &clone] {
+            c.get_tip_hash().await.unwrap();
+        }
+        // Burst of 2, then 2 more at 20/s: ~100 ms.
+        assert!(start.elapsed() >= Duration::from_millis(90));
+        api_mock.assert_hits(4);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_tightens_after_429() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(429).body("slow down");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .rate_limit(RateLimit::new(10.0, 5))
+            .build()
+            .unwrap();
+        assert_eq!(client.current_rate_limit(), Some(10.0));
+        let err = client.get_tip_height().await.unwrap_err();
+        assert!(matches!(err, Error::RateLimited { .. }), "got {err:?}");
+        assert_eq!(client.clone().current_rate_limit(), Some(5.0));
+        assert_eq!(
+            Client::new_public("http://x").unwrap().current_rate_limit(),
+            None
+        );
+    }
+
+    // ── Batch lookups ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_txs_keeps_order_and_isolates_failures() {
+        let server = MockServer::start();
+        let txid = "f5de79f0312d803666e3a83f12423cc5825227ee055c56f2d2b58a1d741f8713";
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{}", txid));
+            then.status(200)
+                .header("content-type", "application/json")
+                .body_from_file("src/testdata/transaction.json");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tx/missing");
+            then.status(404).body("Transaction not found");
+        });
+
+        let client = Client::builder(&server.base_url())
+            .batch_concurrency(2)
+            .build()
+            .unwrap();
+        let results = client.get_txs(&[txid, "missing", txid]).await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].as_ref().unwrap().txid, txid);
+        assert!(matches!(results[1], Err(Error::Http { status: 404, .. })));
+        assert_eq!(results[2].as_ref().unwrap().txid, txid);
+    }
+
+    #[tokio::test]
+    async fn test_batch_respects_concurrency_cap() {
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(GET).path_contains("/status");
+            then.status(200)
+                .body(r#"{"confirmed": false}"#)
+                .delay(Duration::from_millis(100));
+        });
+
+        let client = Client::builder(&server.base_url())
+            .batch_concurrency(2)
+            .build()
+            .unwrap();
+        let txids: Vec<String> = (0..4).map(|i| format!("{i:064x}")).collect();
+        let start = std::time::Instant::now();
+        let results = client.get_tx_statuses(&txids).await;
+
+        // 4 requests, 2 at a time, 100 ms each: at least two rounds.
+        assert!(start.elapsed() >= Duration::from_millis(190));
+        assert!(results.iter().all(|r| !r.as_ref().unwrap().confirmed));
+        api_mock.assert_hits(4);
+    }
+
+    #[tokio::test]
+    async fn test_get_outspends_many_mocked() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/outspends");
+            then.status(200)
+                .header("content-type", "application/json")
+                .body_from_file("src/testdata/outspends.json");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let results = client.get_outspends_many(&["aa", "bb"]).await;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.as_ref().unwrap().len() == 2));
+    }
+
+    #[test]
+    fn test_batch_futures_are_send() {
+        // Async request handlers need `for<'a> Send` futures; borrowing the
+        // caller's slice through a closure breaks that (see `batch::owned`).
+        fn assert_send<T: Send>(_: &T) {}
+        let client = Client::new_public("http://localhost").unwrap();
+        let txids = vec!["aa".to_string()];
+        assert_send(&client.get_txs(&txids));
+        assert_send(&client.get_tx_statuses(&["aa"]));
+        assert_send(&client.get_outspends_many(&txids));
+    }
+
+    // ── Paginated streams ───────────────────────────────────────────────────
+
+    /// A JSON array of copies of the fixture transaction with the given txids.
+    fn tx_page<I: IntoIterator<Item = String>>(txids: I) -> String {
+        let tx: serde_json::Value =
+            serde_json::from_str(include_str!("testdata/transaction.json")).unwrap();
+        let page: Vec<serde_json::Value> = txids
+            .into_iter()
+            .map(|txid| {
+                let mut tx = tx.clone();
+                tx["txid"] = txid.into();
+                tx
+            })
+            .collect();
+        serde_json::to_string(&page).unwrap()
+    }
+
+    fn txid(n: usize) -> String {
+        format!("{n:064x}")
+    }
+
+    #[tokio::test]
+    async fn test_address_txs_stream_yields_mempool_then_pages_chain() {
+        let server = MockServer::start();
+        let addr = "tb1qtest";
+        let mempool = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/address/{addr}/txs/mempool"));
+            then.status(200).body(tx_page([txid(1000)]));
+        });
+        let first = server.mock(|when, then| {
+            when.method(GET).path(format!("/address/{addr}/txs/chain"));
+            then.status(200).body(tx_page((0..25).map(txid)));
+        });
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/address/{addr}/txs/chain/{}", txid(24)));
+            then.status(200).body(tx_page((25..30).map(txid)));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let txs = client
+            .address_txs_stream(addr)
+            .collect_all(None)
+            .await
+            .unwrap();
+
+        let txids: Vec<String> = txs.into_iter().map(|tx| tx.txid).collect();
+        let expected: Vec<String> = std::iter::once(txid(1000))
+            .chain((0..30).map(txid))
+            .collect();
+        assert_eq!(txids, expected);
+        mempool.assert_hits(1);
+        first.assert_hits(1);
+        // The short second page ends the stream without another request.
+        second.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_address_txs_stream_is_lazy_and_capped() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/txs/mempool");
+            then.status(200).body("[]");
+        });
+        let first = server.mock(|when, then| {
+            when.method(GET).path("/address/a/txs/chain");
+            then.status(200).body(tx_page((0..25).map(txid)));
+        });
+        let second = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/address/a/txs/chain/{}", txid(24)));
+            then.status(200).body(tx_page((25..50).map(txid)));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let txs = client
+            .address_txs_stream("a")
+            .collect_all(Some(10))
+            .await
+            .unwrap();
+
+        assert_eq!(txs.len(), 10);
+        first.assert_hits(1);
+        second.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_address_txs_stream_skips_txs_that_confirmed_mid_walk() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/txs/mempool");
+            then.status(200).body(tx_page([txid(7)]));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/address/a/txs/chain");
+            then.status(200).body(tx_page((5..10).map(txid)));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let txs = client
+            .address_txs_stream("a")
+            .collect_all(None)
+            .await
+            .unwrap();
+
+        let txids: Vec<String> = txs.into_iter().map(|tx| tx.txid).collect();
+        assert_eq!(txids, [7, 5, 6, 8, 9].map(txid));
+    }
+
+    #[tokio::test]
+    async fn test_address_txs_stream_ends_after_error() {
+        use futures::StreamExt;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/txs/mempool");
+            then.status(200).body(tx_page([txid(1)]));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/address/a/txs/chain");
+            then.status(500).body("boom");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut stream = client.address_txs_stream("a");
+        assert_eq!(stream.next().await.unwrap().unwrap().txid, txid(1));
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(Error::Http { status: 500, .. }))
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_asset_txs_stream_pages_by_last_txid() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/asset/x/txs/mempool");
+            then.status(200).body("[]");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/asset/x/txs/chain");
+            then.status(200).body(tx_page((0..25).map(txid)));
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/asset/x/txs/chain/{}", txid(24)));
+            then.status(200).body("[]");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let txs = client
+            .asset_txs_stream("x")
+            .collect_all(None)
+            .await
+            .unwrap();
+        assert_eq!(txs.len(), 25);
+    }
+
+    #[tokio::test]
+    async fn test_block_txs_stream_stops_at_tx_count() {
+        let server = MockServer::start();
+        let mut block: serde_json::Value =
+            serde_json::from_str(include_str!("testdata/block.json")).unwrap();
+        block["tx_count"] = 50.into();
+        server.mock(|when, then| {
+            when.method(GET).path("/block/h");
+            then.status(200).body(block.to_string());
+        });
+        let pages = [0, 25].map(|start| {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/block/h/txs/{start}"));
+                then.status(200)
+                    .body(tx_page((start..start + 25).map(txid)));
+            })
+        });
+        // A tx_count that is a multiple of 25 must not probe past the end.
+        let past_end = server.mock(|when, then| {
+            when.method(GET).path("/block/h/txs/50");
+            then.status(404).body("start index out of range");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let txs = client
+            .block_txs_stream("h")
+            .collect_all(None)
+            .await
+            .unwrap();
+
+        let txids: Vec<String> = txs.into_iter().map(|tx| tx.txid).collect();
+        assert_eq!(txids, (0..50).map(txid).collect::<Vec<_>>());
+        pages.iter().for_each(|page| page.assert_hits(1));
+        past_end.assert_hits(0);
+    }
+
+    #[tokio::test]
+    async fn test_blocks_stream_walks_down_to_genesis() {
+        let server = MockServer::start();
+        let block: serde_json::Value =
+            serde_json::from_str(include_str!("testdata/block.json")).unwrap();
+        let page = |heights: std::ops::RangeInclusive<u64>| {
+            let blocks: Vec<serde_json::Value> = heights
+                .rev()
+                .map(|h| {
+                    let mut b = block.clone();
+                    b["height"] = h.into();
+                    b
+                })
+                .collect();
+            serde_json::to_string(&blocks).unwrap()
+        };
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks");
+            then.status(200).body(page(5..=14));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks/4");
+            then.status(200).body(page(0..=4));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let blocks = client.blocks_stream(None).collect_all(None).await.unwrap();
+
+        let heights: Vec<u64> = blocks.iter().map(|b| b.height).collect();
+        assert_eq!(heights, (0..=14).rev().collect::<Vec<_>>());
+    }
+
+    // ── Scripthash endpoints ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_scripthash_endpoints_mocked() {
+        let server = MockServer::start();
+        let hash = scripthash("0014f6b5212642a8b9e83693e5b382d6a6c561763c0a").unwrap();
+        let utxo_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/scripthash/{hash}/utxo"));
+            then.status(200)
+                .header("content-type", "application/json")
+                .body_from_file("src/testdata/utxos.json");
+        });
+        let chain_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/scripthash/{hash}/txs/chain/{}", txid(3)));
+            then.status(200).body(tx_page([txid(4)]));
+        });
+        let mempool_mock = server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/scripthash/{hash}/txs/mempool"));
+            then.status(200).body("[]");
+        });
+        let txs_mock = server.mock(|when, then| {
+            when.method(GET).path(format!("/scripthash/{hash}/txs"));
+            then.status(200).body(tx_page([txid(1), txid(2)]));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        assert!(!client.get_scripthash_utxos(&hash).await.unwrap().is_empty());
+        let chain = client
+            .get_scripthash_txs_chain(&hash, Some(&txid(3)))
+            .await
+            .unwrap();
+        assert_eq!(chain[0].txid, txid(4));
+        assert!(client
+            .get_scripthash_mempool_txs(&hash)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(client.get_scripthash_txs(&hash).await.unwrap().len(), 2);
+
+        utxo_mock.assert();
+        chain_mock.assert();
+        mempool_mock.assert();
+        txs_mock.assert();
+    }
+
+    // ── Merkle proofs / SPV ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_merkle_proof_verifies_against_fetched_header() {
+        let server = MockServer::start();
+        let txid = "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4";
+        let block_hash = "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506";
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}/merkle-proof"));
+            then.status(200)
+                .header("content-type", "application/json")
+                .body_from_file("src/testdata/merkle_proof.json");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/block-height/100000");
+            then.status(200).body(block_hash);
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/block/{block_hash}/header"));
+            then.status(200).body("0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let proof = client.get_tx_merkle_proof(txid).await.unwrap();
+        assert_eq!(proof.block_height, 100_000);
+        assert_eq!(proof.pos, 2);
+
+        let hash = client
+            .get_block_hash_from_height(proof.block_height)
+            .await
+            .unwrap();
+        let header = client.get_block_header(&hash).await.unwrap();
+        assert!(proof.verify_header(txid, &header).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_block_header_matches_block_json() {
+        let server = MockServer::start();
+        let hash = "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506";
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/block/{hash}"));
+            then.status(200)
+                .header("content-type", "application/json")
+                .body_from_file("src/testdata/block_100000.json");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/block/{hash}/header"));
+            then.status(200).body("0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut block = client.get_block(hash).await.unwrap();
+        let header = BlockHeader::from_hex(&client.get_block_header(hash).await.unwrap()).unwrap();
+
+        assert_eq!(header.block_hash(), hash);
+        assert!(header.check_pow());
+        assert!(header.matches_block(&block));
+
+        // A fabricated field in the JSON no longer matches the header.
+        block.merkle_root = "00".repeat(32);
+        assert!(!header.matches_block(&block));
+    }
+
+    // ── Header chain ────────────────────────────────────────────────────────
+
+    /// Mines `count` blocks on top of `parent` at regtest difficulty (about
+    /// one in two nonces passes). `salt` makes competing branches differ.
+    fn mine_chain(parent: &Block, count: u64, salt: u64) -> Vec<Block> {
+        let mut chain: Vec<Block> = Vec::new();
+        for _ in 0..count {
+            let prev = chain.last().unwrap_or(parent);
+            let height = prev.height + 1;
+            let mut block = Block {
+                id: String::new(),
+                height,
+                version: 0x2000_0000,
+                timestamp: 1_700_000_000 + height,
+                tx_count: 1,
+                size: 250,
+                weight: 1000,
+                merkle_root: format!("{:064x}", (salt << 32) | height),
+                previousblockhash: Some(prev.id.clone()),
+                nonce: 0,
+                bits: 0x207f_ffff,
+            };
+            let header = loop {
+                let header = BlockHeader::from_block(&block).unwrap();
+                if header.check_pow() {
+                    break header;
+                }
+                block.nonce += 1;
+            };
+            block.id = header.block_hash();
+            chain.push(block);
+        }
+        chain
+    }
+
+    /// A mined chain starting at height 100.
+    fn mine_chain_from_100(count: u64, salt: u64) -> Vec<Block> {
+        let mut base = mine_chain(&mine_chain_root(), 1, salt);
+        let rest = mine_chain(&base[0], count - 1, salt);
+        base.extend(rest);
+        base
+    }
+
+    fn mine_chain_root() -> Block {
+        Block {
+            id: "00".repeat(32),
+            height: 99,
+            version: 0x2000_0000,
+            timestamp: 1_700_000_000,
+            tx_count: 1,
+            size: 250,
+            weight: 1000,
+            merkle_root: "00".repeat(32),
+            previousblockhash: None,
+            nonce: 0,
+            bits: 0x207f_ffff,
+        }
+    }
+
+    fn block_json(b: &Block) -> serde_json::Value {
+        serde_json::json!({
+            "id": b.id, "height": b.height, "version": b.version,
+            "timestamp": b.timestamp, "tx_count": b.tx_count, "size": b.size,
+            "weight": b.weight, "merkle_root": b.merkle_root,
+            "previousblockhash": b.previousblockhash, "nonce": b.nonce, "bits": b.bits,
+        })
+    }
+
+    /// Serves `chain` as the server's best chain: tip, height → hash,
+    /// `block/:hash` and `blocks/:height` listings.
+    fn serve_chain<'a>(server: &'a MockServer, chain: &[Block]) -> Vec<httpmock::Mock<'a>> {
+        let tip = chain.last().unwrap();
+        let mut mocks = vec![
+            server.mock(|when, then| {
+                when.method(GET).path("/blocks/tip/height");
+                then.status(200).body(tip.height.to_string());
+            }),
+            server.mock(|when, then| {
+                when.method(GET).path("/blocks/tip/hash");
+                then.status(200).body(&tip.id);
+            }),
+        ];
+        for (i, block) in chain.iter().enumerate() {
+            let page: Vec<serde_json::Value> = chain[i.saturating_sub(9)..=i]
+                .iter()
+                .rev()
+                .map(block_json)
+                .collect();
+            mocks.push(server.mock(|when, then| {
+                when.method(GET)
+                    .path(format!("/block-height/{}", block.height));
+                then.status(200).body(&block.id);
+            }));
+            mocks.push(server.mock(|when, then| {
+                when.method(GET).path(format!("/block/{}", block.id));
+                then.status(200).body(block_json(block).to_string());
+            }));
+            mocks.push(server.mock(|when, then| {
+                when.method(GET).path(format!("/blocks/{}", block.height));
+                then.status(200)
+                    .body(serde_json::Value::from(page).to_string());
+            }));
+        }
+        mocks
+    }
+
+    fn connected_heights(events: &[ChainEvent]) -> Vec<u64> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                ChainEvent::Connected(entry) => Some(entry.height),
+                ChainEvent::Reorg { .. } => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_header_chain_syncs_across_pages_then_idles() {
+        let server = MockServer::start();
+        let chain = mine_chain_from_100(26, 1);
+        let _mocks = serve_chain(&server, &chain);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut headers = HeaderChain::new(client, MemoryStore::new(), 100);
+
+        let events = headers.sync().await.unwrap();
+        assert_eq!(connected_heights(&events), (100..=125).collect::<Vec<_>>());
+        assert_eq!(headers.tip().await.unwrap().unwrap().hash, chain[25].id);
+        assert_eq!(headers.store().len(), 26);
+
+        assert!(headers.sync().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_header_chain_reports_reorg_with_fork_point() {
+        let server = MockServer::start();
+        let chain_a = mine_chain_from_100(11, 1); // 100..=110
+        let mocks = serve_chain(&server, &chain_a);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut headers = HeaderChain::new(client, MemoryStore::new(), 100);
+        headers.sync().await.unwrap();
+
+        // The server switches to a branch forking after height 107.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mut chain_b = chain_a[..=7].to_vec();
+        chain_b.extend(mine_chain(&chain_a[7], 5, 2)); // 108..=112
+        let _mocks = serve_chain(&server, &chain_b);
+
+        let events = headers.sync().await.unwrap();
+        match &events[0] {
+            ChainEvent::Reorg {
+                fork_point,
+                disconnected,
+            } => {
+                assert_eq!(fork_point.height, 107);
+                assert_eq!(fork_point.hash, chain_a[7].id);
+                let gone: Vec<&str> = disconnected.iter().map(|e| e.hash.as_str()).collect();
+                assert_eq!(gone, [&chain_a[10].id, &chain_a[9].id, &chain_a[8].id]);
+            }
+            other => panic!("expected a reorg first, got {other:?}"),
+        }
+        assert_eq!(connected_heights(&events), (108..=112).collect::<Vec<_>>());
+        assert_eq!(headers.tip().await.unwrap().unwrap().hash, chain_b[12].id);
+    }
+
+    #[tokio::test]
+    async fn test_header_chain_rejects_block_not_matching_its_id() {
+        let server = MockServer::start();
+        let chain = mine_chain_from_100(5, 1);
+        let mut bad = chain.clone();
+        bad[3].merkle_root = "ab".repeat(32); // fabricated after mining
+        let mocks = serve_chain(&server, &bad);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut headers = HeaderChain::new(client, MemoryStore::new(), 100);
+
+        // The bad block is an error, even though blocks before it were stored…
+        let err = headers.sync().await.unwrap_err();
+        assert!(
+            matches!(err, Error::InvalidChain(ref m) if m.contains("height 103")),
+            "{err}"
+        );
+        assert_eq!(headers.tip().await.unwrap().unwrap().height, 102);
+
+        // …and their events come with the next successful sync.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let _mocks = serve_chain(&server, &chain);
+        let events = headers.sync().await.unwrap();
+        assert_eq!(connected_heights(&events), (100..=104).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn test_header_chain_waits_for_lagging_server() {
+        let server = MockServer::start();
+        let chain = mine_chain_from_100(11, 1); // 100..=110
+        let mocks = serve_chain(&server, &chain);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut headers = HeaderChain::new(client, MemoryStore::new(), 100);
+        headers.sync().await.unwrap();
+
+        // A backend three blocks behind is not a reorg.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mocks = serve_chain(&server, &chain[..=7]);
+        assert!(headers.sync().await.unwrap().is_empty());
+        assert_eq!(headers.store().len(), 11);
+
+        // Once it has caught up and moved on, only the new blocks connect.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mut longer = chain.clone();
+        longer.extend(mine_chain(&chain[10], 2, 1));
+        let _mocks = serve_chain(&server, &longer);
+        let events = headers.sync().await.unwrap();
+        assert_eq!(connected_heights(&events), vec![111, 112]);
+        assert!(events.iter().all(|e| matches!(e, ChainEvent::Connected(_))));
+    }
+
+    // ── Block subscription ──────────────────────────────────────────────────
+
+    /// Points `blocks/tip/hash` at `hash`. The new mock is registered before
+    /// the old one is dropped so a concurrent poll never sees a gap.
+    fn move_tip<'a>(
+        server: &'a MockServer,
+        old: Option<httpmock::Mock<'a>>,
+        hash: &str,
+    ) -> httpmock::Mock<'a> {
+        let new = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/hash");
+            then.status(200).body(hash);
+        });
+        if let Some(mut old) = old {
+            old.delete();
+        }
+        new
+    }
+
+    fn serve_blocks(server: &MockServer, blocks: &[Block]) {
+        for block in blocks {
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/block/{}", block.id));
+                then.status(200).body(block_json(block).to_string());
+            });
+        }
+    }
+
+    async fn next_event(sub: &mut BlockSubscription) -> (char, u64, String) {
+        use futures::StreamExt;
+        let event = tokio::time::timeout(Duration::from_secs(5), sub.next())
+            .await
+            .expect("no event within 5s")
+            .expect("subscription ended")
+            .unwrap();
+        match event {
+            BlockEvent::Connected(b) => ('+', b.height, b.id),
+            BlockEvent::Disconnected(b) => ('-', b.height, b.id),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_blocks_fills_gaps_and_unwinds_reorgs() {
+        let server = MockServer::start();
+        let chain_a = mine_chain_from_100(4, 1); // 100..=103
+        let chain_b = mine_chain(&chain_a[1], 3, 2); // 102'..=104' on 101
+        serve_blocks(&server, &chain_a);
+        serve_blocks(&server, &chain_b);
+        let tip = move_tip(&server, None, &chain_a[1].id);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut sub = client.subscribe_blocks(Duration::from_millis(20));
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 101, chain_a[1].id.clone())
+        );
+
+        // Two blocks arrive between polls: both are reported, in order.
+        let tip = move_tip(&server, Some(tip), &chain_a[3].id);
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 102, chain_a[2].id.clone())
+        );
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 103, chain_a[3].id.clone())
+        );
+
+        // A longer branch forking after 101 replaces 102 and 103.
+        let _tip = move_tip(&server, Some(tip), &chain_b[2].id);
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('-', 103, chain_a[3].id.clone())
+        );
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('-', 102, chain_a[2].id.clone())
+        );
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 102, chain_b[0].id.clone())
+        );
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 103, chain_b[1].id.clone())
+        );
+        assert_eq!(
+            next_event(&mut sub).await,
+            ('+', 104, chain_b[2].id.clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_blocks_waits_for_a_lagging_server() {
+        let server = MockServer::start();
+        let chain = mine_chain_from_100(4, 1); // 100..=103
+        serve_blocks(&server, &chain);
+        let tip = move_tip(&server, None, &chain[1].id);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut sub = client.subscribe_blocks(Duration::from_millis(20));
+        assert_eq!(next_event(&mut sub).await, ('+', 101, chain[1].id.clone()));
+        let tip = move_tip(&server, Some(tip), &chain[2].id);
+        assert_eq!(next_event(&mut sub).await, ('+', 102, chain[2].id.clone()));
+
+        // A server still at 101 answers a few polls, then catches up again.
+        let lagging = move_tip(&server, Some(tip), &chain[1].id);
+        let polled = tokio::time::timeout(Duration::from_secs(5), async {
+            while lagging.hits_async().await < 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        });
+        let consumer = async {
+            // Nothing may be queued while the server lags.
+            tokio::time::timeout(Duration::from_millis(200), next_event(&mut sub)).await
+        };
+        let (polled, queued) = tokio::join!(polled, consumer);
+        polled.expect("subscription stopped polling");
+        assert!(queued.is_err(), "unexpected event {queued:?}");
+        let tip = move_tip(&server, Some(lagging), &chain[2].id);
+        let _tip = move_tip(&server, Some(tip), &chain[3].id);
+        assert_eq!(next_event(&mut sub).await, ('+', 103, chain[3].id.clone()));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_blocks_yields_poll_errors_and_continues() {
+        use futures::StreamExt;
+
+        let server = MockServer::start();
+        let chain = mine_chain_from_100(2, 1);
+        serve_blocks(&server, &chain);
+        let mut failing = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/hash");
+            then.status(503).body("overloaded");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut sub = client.subscribe_blocks(Duration::from_millis(20));
+        assert!(matches!(
+            sub.next().await,
+            Some(Err(Error::Http { status: 503, .. }))
+        ));
+
+        failing.delete();
+        let _tip = move_tip(&server, None, &chain[1].id);
+        assert_eq!(next_event(&mut sub).await, ('+', 101, chain[1].id.clone()));
+    }
+
+    // ── Transaction watcher ─────────────────────────────────────────────────
+
+    /// The fixture transaction as `txid`, with the given status.
+    fn tx_with_status(txid: &str, status: serde_json::Value) -> String {
+        let mut tx: serde_json::Value =
+            serde_json::from_str(include_str!("testdata/transaction.json")).unwrap();
+        tx["txid"] = txid.into();
+        tx["status"] = status;
+        tx.to_string()
+    }
+
+    fn confirmed_at(height: u64, hash: &str) -> serde_json::Value {
+        serde_json::json!({
+            "confirmed": true, "block_height": height, "block_hash": hash,
+            "block_time": 1_700_000_000u64,
+        })
+    }
+
+    fn mock_tip_height(server: &MockServer, height: u64) -> httpmock::Mock<'_> {
+        server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/height");
+            then.status(200).body(height.to_string());
+        })
+    }
+
+    #[tokio::test]
+    async fn test_tx_watcher_follows_mempool_to_depth() {
+        let server = MockServer::start();
+        let txid = txid(1);
+        let mut tx = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}"));
+            then.status(200).body(tx_with_status(
+                &txid,
+                serde_json::json!({"confirmed": false}),
+            ));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut watcher = TxWatcher::new(client).confirmations(3);
+        watcher.watch(&txid);
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [TxEvent::Seen { txid: txid.clone() }]
+        );
+        assert!(watcher.poll().await.unwrap().is_empty());
+
+        tx.delete();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}"));
+            then.status(200)
+                .body(tx_with_status(&txid, confirmed_at(100, "bb")));
+        });
+        let mut tip = mock_tip_height(&server, 100);
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [TxEvent::Confirmed {
+                txid: txid.clone(),
+                height: 100,
+                block_hash: "bb".into()
+            }]
+        );
+
+        // Once confirmed, only the cheap status endpoint is polled.
+        let status = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}/status"));
+            then.status(200).body(confirmed_at(100, "bb").to_string());
+        });
+        tip.delete();
+        mock_tip_height(&server, 102);
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [TxEvent::ReachedDepth {
+                txid: txid.clone(),
+                confirmations: 3
+            }]
+        );
+        status.assert_hits(1);
+        assert_eq!(watcher.watching().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_tx_watcher_reports_reorg_then_reconfirmation() {
+        let server = MockServer::start();
+        let txid = txid(2);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}"));
+            then.status(200)
+                .body(tx_with_status(&txid, confirmed_at(100, "aa")));
+        });
+        mock_tip_height(&server, 100);
+        let mut status = server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}/status"));
+            then.status(200).body(confirmed_at(101, "cc").to_string());
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut watcher = TxWatcher::new(client);
+        watcher.watch(&txid);
+        watcher.poll().await.unwrap();
+
+        // The block moved: reorged out of `aa`, confirmed again in `cc`.
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [
+                TxEvent::Reorged {
+                    txid: txid.clone(),
+                    height: 100,
+                    block_hash: "aa".into()
+                },
+                TxEvent::Confirmed {
+                    txid: txid.clone(),
+                    height: 101,
+                    block_hash: "cc".into()
+                },
+            ]
+        );
+
+        // Then back to unconfirmed: reorged again, still watched.
+        status.delete();
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}/status"));
+            then.status(200).body(r#"{"confirmed": false}"#);
+        });
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [TxEvent::Reorged {
+                txid: txid.clone(),
+                height: 101,
+                block_hash: "cc".into()
+            }]
+        );
+        assert_eq!(watcher.watching().collect::<Vec<_>>(), [txid.as_str()]);
+    }
+
+    #[tokio::test]
+    async fn test_tx_watcher_stream_drops_missing_tx_and_ends() {
+        use futures::StreamExt;
+
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/tx/");
+            then.status(404).body("Transaction not found");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut watcher = TxWatcher::new(client).drop_timeout(Duration::from_millis(50));
+        watcher.watch(&txid(3));
+        let events: Vec<TxEvent> = watcher
+            .into_stream(Duration::from_millis(20))
+            .map(Result::unwrap)
+            .collect()
+            .await;
+        assert_eq!(events, [TxEvent::Dropped { txid: txid(3) }]);
+    }
+
+    #[tokio::test]
+    async fn test_tx_watcher_poll_error_changes_nothing() {
+        let server = MockServer::start();
+        let mut failing = server.mock(|when, then| {
+            when.method(GET).path_contains("/tx/");
+            then.status(500).body("boom");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let mut watcher = TxWatcher::new(client);
+        watcher.watch(&txid(4));
+        assert!(matches!(
+            watcher.poll().await,
+            Err(Error::Http { status: 500, .. })
+        ));
+
+        failing.delete();
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/tx/");
+            then.status(200).body(tx_with_status(
+                &txid(4),
+                serde_json::json!({"confirmed": false}),
+            ));
+        });
+        assert_eq!(
+            watcher.poll().await.unwrap(),
+            [TxEvent::Seen { txid: txid(4) }]
+        );
+    }
+
+    // ── Broadcast and confirm ───────────────────────────────────────────────
+
+    /// A one-input segwit transaction spending `aa..aa:1`, and its txid.
+    fn spend_tx() -> (String, String) {
+        let hex = format!(
+            "02000000000101{}0100000000ffffffff01e803000000000000160014{}0247{}21{}00000000",
+            "aa".repeat(32),
+            "bb".repeat(20),
+            "cc".repeat(0x47),
+            "dd".repeat(0x21)
+        );
+        let txid = rawtx::RawTx::from_hex(&hex).unwrap().txid;
+        (hex, txid)
+    }
+
+    fn mock_broadcast<'a>(server: &'a MockServer, status: u16, body: &str) -> httpmock::Mock<'a> {
+        server.mock(|when, then| {
+            when.method(POST).path("/tx");
+            then.status(status).body(body);
+        })
+    }
+
+    fn mock_input_spent_by<'a>(server: &'a MockServer, spender: &str) -> httpmock::Mock<'a> {
+        let body = serde_json::json!({
+            "spent": true, "txid": spender, "vin": 0, "status": {"confirmed": false},
+        });
+        server.mock(|when, then| {
+            when.method(GET)
+                .path(format!("/tx/{}/outspend/1", "aa".repeat(32)));
+            then.status(200).body(body.to_string());
+        })
+    }
+
+    fn fast_policy(policy: BroadcastPolicy) -> BroadcastPolicy {
+        policy
+            .poll_interval(Duration::from_millis(10))
+            .timeout(Duration::from_secs(5))
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_waits_for_depth() {
+        let server = MockServer::start();
+        let (hex, txid) = spend_tx();
+        mock_broadcast(&server, 200, &txid);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}"));
+            then.status(200)
+                .body(tx_with_status(&txid, confirmed_at(100, "bb")));
+        });
+        mock_tip_height(&server, 101);
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let outcome = client
+            .broadcast_and_confirm(&hex, fast_policy(BroadcastPolicy::confirmations(2)))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            BroadcastOutcome::Confirmed {
+                txid,
+                height: 100,
+                block_hash: "bb".into(),
+                confirmations: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_propagated_and_timeout() {
+        let server = MockServer::start();
+        let (hex, txid) = spend_tx();
+        mock_broadcast(&server, 200, &txid);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{txid}"));
+            then.status(200).body(tx_with_status(
+                &txid,
+                serde_json::json!({"confirmed": false}),
+            ));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let outcome = client
+            .broadcast_and_confirm(&hex, fast_policy(BroadcastPolicy::propagated()))
+            .await
+            .unwrap();
+        assert_eq!(outcome, BroadcastOutcome::Propagated { txid: txid.clone() });
+
+        let policy =
+            fast_policy(BroadcastPolicy::confirmations(1)).timeout(Duration::from_millis(50));
+        let outcome = client.broadcast_and_confirm(&hex, policy).await.unwrap();
+        assert_eq!(outcome, BroadcastOutcome::TimedOut { txid, seen: true });
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_rejects_txid_mismatch() {
+        let server = MockServer::start();
+        let (hex, id) = spend_tx();
+        mock_broadcast(&server, 200, &txid(7));
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let err = client
+            .broadcast_and_confirm(&hex, BroadcastPolicy::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::TxidMismatch { expected, actual } if expected == id && actual == txid(7)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_detects_replacement() {
+        let server = MockServer::start();
+        let (hex, id) = spend_tx();
+        mock_broadcast(&server, 200, &id);
+        server.mock(|when, then| {
+            when.method(GET).path(format!("/tx/{id}"));
+            then.status(404).body("Transaction not found");
+        });
+        let outspend = mock_input_spent_by(&server, &txid(9));
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let outcome = client
+            .broadcast_and_confirm(&hex, fast_policy(BroadcastPolicy::default()))
+            .await
+            .unwrap();
+        assert_eq!(
+            outcome,
+            BroadcastOutcome::Conflicted {
+                txid: id,
+                conflicting_txid: txid(9),
+                outpoint: OutPoint {
+                    txid: "aa".repeat(32),
+                    vout: 1
+                },
+            }
+        );
+        outspend.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_explains_rejected_double_spend() {
+        let server = MockServer::start();
+        let (hex, _) = spend_tx();
+        mock_broadcast(
+            &server,
+            400,
+            "sendrawtransaction RPC error: {\"code\":-26,\"message\":\"txn-mempool-conflict\"}",
+        );
+        mock_input_spent_by(&server, &txid(9));
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let outcome = client
+            .broadcast_and_confirm(&hex, BroadcastPolicy::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            outcome,
+            BroadcastOutcome::Conflicted { conflicting_txid, .. } if conflicting_txid == txid(9)
+        ));
+
+        // Without a conflicting spender, the rejection itself is returned.
+        let server = MockServer::start();
+        mock_broadcast(&server, 400, "bad-txns-in-belowout");
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let err = client
+            .broadcast_and_confirm(&hex, BroadcastPolicy::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.status(), Some(400));
+        assert!(matches!(
+            client
+                .broadcast_and_confirm("00", BroadcastPolicy::default())
+                .await,
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_reports_refused_replacement_as_conflict() {
+        let server = MockServer::start();
+        let (hex, _) = spend_tx();
+        mock_broadcast(
+            &server,
+            400,
+            "sendrawtransaction RPC error: {\"code\":-26,\"message\":\"insufficient fee, rejecting replacement 0a0b, less fees than conflicting txs; 0.00001 < 0.00002\"}",
+        );
+        let outspend = mock_input_spent_by(&server, &txid(9));
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let outcome = client
+            .broadcast_and_confirm(&hex, BroadcastPolicy::default())
+            .await
+            .unwrap();
+        assert!(matches!(
+            outcome,
+            BroadcastOutcome::Conflicted { conflicting_txid, .. } if conflicting_txid == txid(9)
+        ));
+        outspend.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_and_confirm_watches_already_known_tx() {
+        for reason in ["txn-already-known", "txn-already-in-mempool"] {
+            let server = MockServer::start();
+            let (hex, txid) = spend_tx();
+            mock_broadcast(
+                &server,
+                400,
+                &format!("sendrawtransaction RPC error: {{\"code\":-27,\"message\":\"{reason}\"}}"),
+            );
+            server.mock(|when, then| {
+                when.method(GET).path(format!("/tx/{txid}"));
+                then.status(200)
+                    .body(tx_with_status(&txid, confirmed_at(100, "bb")));
+            });
+            mock_tip_height(&server, 100);
+
+            let client = Client::new_public(&server.base_url()).unwrap();
+            let outcome = client
+                .broadcast_and_confirm(&hex, fast_policy(BroadcastPolicy::default()))
+                .await
+                .unwrap();
+            assert!(
+                matches!(&outcome, BroadcastOutcome::Confirmed { height: 100, .. }),
+                "{reason}: {outcome:?}"
+            );
+            assert_eq!(outcome.txid(), txid);
+        }
+    }
+
+    // ── Package broadcast ───────────────────────────────────────────────────
+
+    /// The serialized transactions behind the package and mempool-accept
+    /// fixtures: a zero-fee parent and its fee-paying child, plus a
+    /// standalone transaction paying 5 sat/vB and one paying nothing.
+    fn package_tx(name: &str) -> String {
+        let txs: std::collections::HashMap<String, String> =
+            serde_json::from_str(include_str!("testdata/package_txs.json")).unwrap();
+        txs[name].clone()
+    }
+
+    /// `(txid, wtxid)` of a serialized transaction.
+    fn tx_ids(hex: &str) -> (String, String) {
+        let bytes = hashes::decode_hex(hex, "transaction").unwrap();
+        let wtxid = hashes::display_hash(&hashes::sha256d(&bytes));
+        (rawtx::RawTx::from_hex(hex).unwrap().txid, wtxid)
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_package_posts_json_array() {
+        let (parent_hex, child_hex) = (package_tx("parent"), package_tx("child"));
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/txs/package")
+                .header("content-type", "application/json")
+                .json_body(serde_json::json!([parent_hex, child_hex]));
+            then.status(200)
+                .body(include_str!("testdata/package_success.json"));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let result = client
+            .broadcast_package(&[&parent_hex, &child_hex])
+            .await
+            .unwrap();
+        api_mock.assert();
+        assert!(result.is_success());
+        assert_eq!(result.tx_results.len(), 2);
+        assert_eq!(result.replaced_transactions.len(), 1);
+
+        // Results are keyed by wtxid and carry the txid.
+        let (parent_txid, parent_wtxid) = tx_ids(&parent_hex);
+        let (_, child_wtxid) = tx_ids(&child_hex);
+        let parent = &result.tx_results[&parent_wtxid];
+        assert_eq!(parent.txid, parent_txid);
+        assert_eq!(parent.vsize, Some(110));
+        let fees = parent.fees.as_ref().unwrap();
+        assert_eq!(fees.base, 0.0);
+        assert_eq!(fees.effective_includes, [parent_wtxid, child_wtxid.clone()]);
+        let feerate = fees.effective_feerate_sat_vb().unwrap();
+        assert!((feerate - 25.0).abs() < 1e-9, "feerate {feerate}");
+        assert!(parent.error.is_none() && parent.other_wtxid.is_none());
+        let child_fee = result.tx_results[&child_wtxid].fees.as_ref().unwrap().base;
+        assert!((child_fee - 0.000055).abs() < 1e-12);
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_package_reports_per_tx_errors() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/txs/package");
+            then.status(200)
+                .body(include_str!("testdata/package_tx_failed.json"));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let (parent_hex, child_hex) = (package_tx("parent"), package_tx("child"));
+        let result = client
+            .broadcast_package(&[&parent_hex, &child_hex])
+            .await
+            .unwrap();
+        assert!(!result.is_success());
+        assert_eq!(result.package_msg, "transaction failed");
+        let (parent_txid, parent_wtxid) = tx_ids(&parent_hex);
+        assert_eq!(result.tx_results[&parent_wtxid].txid, parent_txid);
+        let mut reasons: Vec<BroadcastRejection> = result
+            .tx_results
+            .values()
+            .map(|r| BroadcastRejection::from_reason(r.error.as_deref().unwrap()))
+            .collect();
+        reasons.sort_by_key(|r| format!("{r:?}"));
+        assert_eq!(
+            reasons,
+            [
+                BroadcastRejection::FeeTooLow,
+                BroadcastRejection::MissingInputs
+            ]
+        );
+        assert!(result.tx_results.values().all(|r| r.fees.is_none()));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_package_classifies_errors() {
+        let server = MockServer::start();
+        let mut rejected = server.mock(|when, then| {
+            when.method(POST).path("/txs/package");
+            then.status(400).body(
+                r#"submitpackage RPC error: {"code":-25,"message":"package topology disallowed. not child-with-parents or parents depend on each other."}"#,
+            );
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let err = client.broadcast_package(&["00", "01"]).await.unwrap_err();
+        assert!(
+            matches!(err, Error::Http { status: 400, .. }),
+            "got {err:?}"
+        );
+        let rejection = err.broadcast_rejection().unwrap();
+        assert_eq!(rejection.code, Some(-25));
+        assert!(rejection.message.starts_with("package topology"));
+
+        // An Esplora without package support.
+        rejected.delete();
+        server.mock(|when, then| {
+            when.method(POST).path("/txs/package");
+            then.status(404).body("Not Found");
+        });
+        let err = client.broadcast_package(&["00"]).await.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Unsupported {
+                    endpoint: "POST txs/package",
+                    ..
+                }
+            ),
+            "got {err:?}"
+        );
+    }
+
+    // ── Mempool acceptance test ─────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_mempool_accept_maps_verdicts() {
+        let (allowed_hex, low_fee_hex) = (package_tx("allowed"), package_tx("low_fee"));
+        let server = MockServer::start();
+        let api_mock = server.mock(|when, then| {
+            when.method(POST)
+                .path("/txs/test")
+                .json_body(serde_json::json!([allowed_hex, low_fee_hex]));
+            then.status(200)
+                .body(include_str!("testdata/mempool_accept.json"));
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let results = client
+            .test_mempool_accept(&[&allowed_hex, &low_fee_hex])
+            .await
+            .unwrap();
+        api_mock.assert();
+        assert_eq!(results.len(), 2);
+        let (txid, wtxid) = tx_ids(&allowed_hex);
+        assert_eq!(results[0].txid, txid);
+        assert_eq!(results[0].wtxid.as_deref(), Some(wtxid.as_str()));
+        assert_eq!(
+            results[0].fees.as_ref().unwrap().effective_includes,
+            [wtxid]
+        );
+        assert!(results[0].allowed);
+        assert_eq!(results[0].vsize, Some(141));
+        assert_eq!(results[0].rejection(), None);
+        let feerate = results[0].fees.as_ref().unwrap().effective_feerate_sat_vb();
+        assert!((feerate.unwrap() - 5.0).abs() < 1e-9);
+        assert!(!results[1].allowed);
+        assert_eq!(
+            results[1].reject_reason.as_deref(),
+            Some("min relay fee not met")
+        );
+        assert_eq!(results[1].rejection(), Some(BroadcastRejection::FeeTooLow));
+    }
+
+    #[tokio::test]
+    async fn test_mempool_accept_fails_over_like_a_get() {
+        let primary = MockServer::start();
+        let backup = MockServer::start();
+        let primary_mock = primary.mock(|when, then| {
+            when.method(POST).path("/txs/test");
+            then.status(503).body("Service Unavailable");
+        });
+        let backup_mock = backup.mock(|when, then| {
+            when.method(POST).path("/txs/test");
+            then.status(200)
+                .body(include_str!("testdata/mempool_accept.json"));
+        });
+
+        let client = Client::builder(&primary.base_url())
+            .fallback(&backup.base_url(), Auth::new_public())
+            .build()
+            .unwrap();
+        let results = client.test_mempool_accept(&["aa", "bb"]).await.unwrap();
+        assert_eq!(results.len(), 2);
+        primary_mock.assert_hits(1);
+        backup_mock.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_mempool_accept_unsupported_backend() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(POST).path("/txs/test");
+            then.status(404).body("Not Found");
+        });
+
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let err = client.test_mempool_accept(&["aa"]).await.unwrap_err();
+        assert!(err.to_string().contains("not supported"), "{err}");
+        match err {
+            Error::Unsupported { endpoint, url } => {
+                assert_eq!(endpoint, "POST txs/test");
+                assert!(url.ends_with("/txs/test"), "url: {url}");
+            }
+            other => panic!("expected Error::Unsupported, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mempool_accept_unsupported_after_retries() {
+        let server = MockServer::start();
+        let mut busy = server.mock(|when, then| {
+            when.method(POST).path("/txs/test");
+            then.status(503).body("Service Unavailable");
+        });
+        let client = Client::builder(&server.base_url())
+            .retry(
+                RetryPolicy::with_max_attempts(3)
+                    .backoff(Duration::from_millis(300), Duration::from_millis(300))
+                    .jitter(false),
+            )
+            .build()
+            .unwrap();
+
+        // The node restarts without the endpoint between attempts.
+        let swap = async {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            busy.delete();
+            server.mock(|when, then| {
+                when.method(POST).path("/txs/test");
+                then.status(404).body("Not Found");
+            })
+        };
+        let (result, _missing) = tokio::join!(client.test_mempool_accept(&["aa"]), swap);
+        let err = result.unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::Unsupported {
+                    endpoint: "POST txs/test",
+                    ..
+                }
+            ),
+            "got {err:?}"
+        );
+    }
+
+    // ── Response cache ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_cache_serves_immutable_and_deep_data() {
+        let server = MockServer::start();
+        let block = server.mock(|when, then| {
+            when.method(GET).path("/block/bb");
+            then.status(200).body(include_str!("testdata/block.json"));
+        });
+        let tx = server.mock(|when, then| {
+            when.method(GET).path("/tx/t1");
+            then.status(200)
+                .body(tx_with_status("t1", confirmed_at(100, "bb")));
+        });
+        let mempool = server.mock(|when, then| {
+            when.method(GET).path("/mempool/txids");
+            then.status(200).body("[]");
+        });
+        mock_tip_height(&server, 105);
+
+        let client = Client::builder(&server.base_url())
+            .cache(CacheConfig::new(1 << 20))
+            .build()
+            .unwrap();
+        let clone = client.clone();
+        client.get_block("bb").await.unwrap();
+        clone.get_block("bb").await.unwrap();
+        block.assert_hits(1);
+
+        // Not known to be deep until the tip has been seen.
+        client.get_tx("t1").await.unwrap();
+        client.get_tip_height().await.unwrap();
+        client.get_tx("t1").await.unwrap();
+        client.get_tx("t1").await.unwrap();
+        tx.assert_hits(2);
+
+        client.get_mempool_txids().await.unwrap();
+        client.get_mempool_txids().await.unwrap();
+        mempool.assert_hits(2);
+
+        let stats = client.cache_stats().unwrap();
+        assert_eq!((stats.hits, stats.misses), (2, 3));
+        assert_eq!(stats.entries, 2);
+        assert!(Client::new_public(&server.base_url())
+            .unwrap()
+            .cache_stats()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cache_skips_shallow_and_drops_on_reorg() {
+        let server = MockServer::start();
+        let status = server.mock(|when, then| {
+            when.method(GET).path("/tx/t1/status");
+            then.status(200).body(confirmed_at(100, "bb").to_string());
+        });
+        let mut tip = mock_tip_height(&server, 101);
+
+        let client = Client::builder(&server.base_url())
+            .cache(CacheConfig::new(1 << 20).min_confirmations(2))
+            .build()
+            .unwrap();
+        client.get_tip_height().await.unwrap();
+        client.get_tx_status("t1").await.unwrap();
+        client.get_tx_status("t1").await.unwrap();
+        status.assert_hits(1);
+
+        // The chain shrinks: height 100 has one confirmation again.
+        tip.delete();
+        mock_tip_height(&server, 100);
+        client.get_tip_height().await.unwrap();
+        client.get_tx_status("t1").await.unwrap();
+        status.assert_hits(2);
+        assert_eq!(client.cache_stats().unwrap().invalidations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cache_drops_orphaned_entries_on_same_height_reorg() {
+        let server = MockServer::start();
+        let chain_a = mine_chain_from_100(11, 1); // 100..=110
+        let mocks = serve_chain(&server, &chain_a);
+        let status = server.mock(|when, then| {
+            when.method(GET).path("/tx/t1/status");
+            then.status(200)
+                .body(confirmed_at(109, &chain_a[9].id).to_string());
+        });
+
+        let client = Client::builder(&server.base_url())
+            .cache(CacheConfig::new(1 << 20).min_confirmations(2))
+            .build()
+            .unwrap();
+        let mut headers = HeaderChain::new(client.clone(), MemoryStore::new(), 100);
+        headers.sync().await.unwrap();
+        client.get_tx_status("t1").await.unwrap();
+        client.get_tx_status("t1").await.unwrap();
+        status.assert_hits(1);
+
+        // A branch forking after 107 replaces 108..=110: the tip height stays.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mut chain_b = chain_a[..=7].to_vec();
+        chain_b.extend(mine_chain(&chain_a[7], 3, 2));
+        let _mocks = serve_chain(&server, &chain_b);
+        headers.sync().await.unwrap();
+
+        client.get_tx_status("t1").await.unwrap();
+        status.assert_hits(2);
+        // The status, and the `block-height/108` and `/109` lookups the
+        // first sync cached, which named orphaned blocks.
+        assert_eq!(client.cache_stats().unwrap().invalidations, 3);
+    }
+
+    #[tokio::test]
+    async fn test_header_chain_sees_reorg_deeper_than_cache_depth() {
+        let server = MockServer::start();
+        let chain_a = mine_chain_from_100(11, 1); // 100..=110
+        let mocks = serve_chain(&server, &chain_a);
+
+        let client = Client::builder(&server.base_url())
+            .cache(CacheConfig::new(1 << 20).min_confirmations(2))
+            .build()
+            .unwrap();
+        let mut headers = HeaderChain::new(client.clone(), MemoryStore::new(), 100);
+        headers.sync().await.unwrap();
+        // The application's own lookups cache every height 2 deep.
+        for height in 100..=109 {
+            client.get_block_hash_from_height(height).await.unwrap();
+        }
+
+        // A branch forking after 105 replaces 106..=110: 5 deep.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mut chain_b = chain_a[..=5].to_vec();
+        chain_b.extend(mine_chain(&chain_a[5], 5, 2));
+        let _mocks = serve_chain(&server, &chain_b);
+
+        let events = headers.sync().await.unwrap();
+        match &events[0] {
+            ChainEvent::Reorg { fork_point, .. } => assert_eq!(fork_point.height, 105),
+            other => panic!("expected a reorg first, got {other:?}"),
+        }
+        assert_eq!(headers.tip().await.unwrap().unwrap().hash, chain_b[10].id);
+        assert_eq!(
+            client.get_block_hash_from_height(109).await.unwrap(),
+            chain_b[9].id
+        );
+    }
+
+    // ── Disk cache ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_disk_cache_warm_start_and_offline() {
+        let dir = std::env::temp_dir().join(format!("esplora-rs-offline-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/block/bb");
+            then.status(200).body(include_str!("testdata/block.json"));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tx/t1");
+            then.status(200)
+                .body(tx_with_status("t1", confirmed_at(100, "bb")));
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/tx/t1/raw");
+            then.status(200).body([1u8, 2, 3]);
+        });
+        mock_tip_height(&server, 105);
+
+        let online = Client::builder(&server.base_url())
+            .disk_cache(DiskCacheConfig::new(&dir, 1 << 20))
+            .build()
+            .unwrap();
+        let block = online.get_block("bb").await.unwrap();
+        online.get_tip_height().await.unwrap();
+        let tx = online.get_tx("t1").await.unwrap();
+        online.get_raw_tx("t1").await.unwrap();
+        drop(online);
+
+        // A fresh client (as after a restart) that never touches the network.
+        let offline = Client::builder(&server.base_url())
+            .disk_cache(DiskCacheConfig::new(&dir, 1 << 20).offline(true))
+            .build()
+            .unwrap();
+        assert_eq!(offline.get_block("bb").await.unwrap().id, block.id);
+        assert_eq!(offline.get_tx("t1").await.unwrap().txid, tx.txid);
+        assert_eq!(offline.get_raw_tx("t1").await.unwrap(), [1u8, 2, 3][..]);
+        let requests = server.mock(|when, _| {
+            when.any_request();
+        });
+
+        let err = offline.get_tip_height().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::OfflineMiss { url } if url.ends_with("/blocks/tip/height")),
+            "{err:?}"
+        );
+        assert!(matches!(
+            offline.broadcast_tx("00").await,
+            Err(Error::OfflineMiss { .. })
+        ));
+        requests.assert_hits(0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_forgets_transactions_from_orphaned_blocks() {
+        let dir =
+            std::env::temp_dir().join(format!("esplora-rs-disk-reorg-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let server = MockServer::start();
+        let chain_a = mine_chain_from_100(11, 1); // 100..=110
+        let mocks = serve_chain(&server, &chain_a);
+        server.mock(|when, then| {
+            when.method(GET).path("/tx/t1");
+            then.status(200)
+                .body(tx_with_status("t1", confirmed_at(107, &chain_a[7].id)));
+        });
+
+        let client = Client::builder(&server.base_url())
+            .disk_cache(DiskCacheConfig::new(&dir, 1 << 20).min_confirmations(2))
+            .build()
+            .unwrap();
+        let mut headers = HeaderChain::new(client.clone(), MemoryStore::new(), 100);
+        headers.sync().await.unwrap();
+        client.get_tx("t1").await.unwrap();
+        let offline = || {
+            Client::builder(&server.base_url())
+                .disk_cache(DiskCacheConfig::new(&dir, 1 << 20).offline(true))
+                .build()
+                .unwrap()
+        };
+        assert!(offline().get_tx("t1").await.is_ok(), "stored 4 deep");
+
+        // A branch forking after 105 orphans the block t1 confirmed in.
+        for mut mock in mocks {
+            mock.delete();
+        }
+        let mut chain_b = chain_a[..=5].to_vec();
+        chain_b.extend(mine_chain(&chain_a[5], 5, 2));
+        let _mocks = serve_chain(&server, &chain_b);
+        headers.sync().await.unwrap();
+        drop((headers, client));
+
+        // After a restart, offline mode doesn't serve the orphaned confirmation.
+        assert!(matches!(
+            offline().get_tx("t1").await,
+            Err(Error::OfflineMiss { .. })
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // ── Request coalescing ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_concurrent_identical_gets_share_one_request() {
+        let server = MockServer::start();
+        let tx = server.mock(|when, then| {
+            when.method(GET).path("/tx/t1");
+            then.status(200)
+                .delay(Duration::from_millis(200))
+                .body(tx_with_status("t1", confirmed_at(100, "bb")));
+        });
+        let hash = server.mock(|when, then| {
+            when.method(GET).path("/blocks/tip/hash");
+            then.status(200)
+                .delay(Duration::from_millis(200))
+                .body("00ff");
+        });
+        let client = Client::new_public(&server.base_url()).unwrap();
+        let clone = client.clone();
+
+        let (a, b, c, d) = tokio::join!(
+            client.get_tx("t1"),
+            clone.get_tx("t1"),
+            client.get_tip_hash(),
+            clone.get_tip_hash(),
+        );
+        assert_eq!(a.unwrap().txid, b.unwrap().txid);
+        assert_eq!((c.unwrap(), d.unwrap()), ("00ff".into(), "00ff".into()));
+        tx.assert_hits(1);
+        hash.assert_hits(1);
+
+        // Sequential calls aren't merged.
+        client.get_tip_hash().await.unwrap();
+        hash.assert_hits(2);
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_get_error_reaches_every_waiter() {
+        let server = MockServer::start();
+        let missing = server.mock(|when, then| {
+            when.method(GET).path("/tx/t1");
+            then.status(404)
+                .delay(Duration::from_millis(200))
+                .body("Transaction not found");
+        });
+        let client = Client::new_public(&server.base_url()).unwrap();
+
+        let (a, b) = tokio::join!(client.get_tx("t1"), client.get_tx("t1"));
+        for err in [a.unwrap_err(), b.unwrap_err()] {
+            assert!(
+                matches!(&err, Error::Http { status: 404, body, .. } if body == "Transaction not found"),
+                "{err:?}"
+            );
+        }
+        missing.assert_hits(1);
+    }
+
+    #[tokio::test]
+    async fn test_coalesced_error_has_the_same_root_for_every_waiter() {
+        // Nothing listens on port 1: every attempt fails to connect.
+        let client = Client::builder("http://127.0.0.1:1/")
+            .retry(
+                RetryPolicy::with_max_attempts(2)
+                    .backoff(Duration::from_millis(50), Duration::from_millis(50)),
+            )
+            .build()
+            .unwrap();
+
+        // Alone, the call ends in `RetriesExhausted`; coalesced, the waiters
+        // get `Shared` handles, since a transport error can't be copied.
+        let alone = client.get_tx("t1").await.unwrap_err();
+        assert!(matches!(alone, Error::RetriesExhausted { .. }), "{alone:?}");
+        let (a, b) = tokio::join!(client.get_tx("t1"), client.get_tx("t1"));
+        let (a, b) = (a.unwrap_err(), b.unwrap_err());
+        assert!(matches!(a, Error::Shared(_)), "{a:?}");
+        for err in [alone, a, b] {
+            assert!(
+                matches!(err.root(), Error::Reqwest(e) if e.is_connect()),
+                "{err:?}"
+            );
+        }
+    }
+
+    // ── Usage and credits ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_usage_stats_track_credits_and_alert() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET)
+                .path("/testnet/api/waterfalls/v2/waterfalls");
+            then.status(200)
+                .header("X-Credits-Remaining", "1005")
+                .body_from_file("src/testdata/waterfalls_v2.json");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path_contains("/testnet/api/tx/");
+            then.status(200)
+                .header("X-Credits-Remaining", "995")
+                .body(tx_with_status("t", confirmed_at(100, "bb")));
+        });
+        let alerts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = alerts.clone();
+        let client = Client::builder(&server.url("/testnet/api"))
+            .credit_alert(CreditAlert::new(1000).on_low(move |_, remaining| {
+                seen.lock().unwrap().push(remaining);
+            }))
+            .build()
+            .unwrap();
+
+        client.get_waterfalls("wpkh(x)", 0, 0).await.unwrap();
+        assert!(alerts.lock().unwrap().is_empty());
+        client.get_tx("t1").await.unwrap();
+        client.clone().get_tx("t2").await.unwrap();
+        assert_eq!(*alerts.lock().unwrap(), [995]);
+
+        let stats = client.usage_stats();
+        assert_eq!(
+            stats.credits_remaining,
+            std::collections::BTreeMap::from([(server.url("/testnet/api/"), 995)])
+        );
+        assert_eq!((stats.calls, stats.estimated_credits), (3, 12));
+        assert_eq!(
+            stats.endpoints.keys().collect::<Vec<_>>(),
+            ["tx/:txid", "waterfalls/v2/waterfalls"]
+        );
+        assert_eq!(stats.endpoints["tx/:txid"].calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_usage_stats_per_backend_with_nested_base_paths() {
+        let server = MockServer::start();
+        server.mock(|when, then| {
+            when.method(GET).path("/api/tx/t1");
+            then.status(503)
+                .header("X-Credits-Remaining", "900")
+                .body("Service Unavailable");
+        });
+        server.mock(|when, then| {
+            when.method(GET).path("/api/testnet/tx/t1");
+            then.status(200)
+                .header("X-Credits-Remaining", "40")
+                .body(tx_with_status("t1", confirmed_at(100, "bb")));
+        });
+
+        let client = Client::builder(&server.url("/api"))
+            .fallback(&server.url("/api/testnet"), Auth::new_public())
+            .build()
+            .unwrap();
+        client.get_tx("t1").await.unwrap();
+
+        // `/api/testnet/tx/t1` belongs to the `/api/testnet/` backend, not
+        // `/api/` with a `testnet/tx/t1` endpoint.
+        let stats = client.usage_stats();
+        assert_eq!(stats.endpoints.keys().collect::<Vec<_>>(), ["tx/:txid"]);
+        assert_eq!(stats.endpoints["tx/:txid"].calls, 2);
+        assert_eq!(
+            stats.credits_remaining,
+            std::collections::BTreeMap::from([
+                (server.url("/api/"), 900),
+                (server.url("/api/testnet/"), 40),
+            ])
+        );
+    }
+
     #[tokio::test]
     async fn test_get_block_live() {
         if !should_run_live_tests() {
@@ -0,0 +1,174 @@
+//! SPV verification of [`MerkleProof`]s.
+//!
+//! A proof from [`crate::Client::get_tx_merkle_proof`] lets a caller rebuild a
+//! block's merkle root from a txid alone. Checking that root against a block
+//! header the caller trusts (for example one from a validated header chain)
+//! confirms the transaction's inclusion without trusting the server's
+//! `status.confirmed` flag. Hashing is done locally; no `bitcoin` dependency.
+
+use crate::hashes::{decode_display_hash, display_hash, sha256d};
+use crate::{Block, BlockHeader, Error, MerkleProof};
+
+impl MerkleProof {
+    /// Rebuilds the merkle root (display hex) implied by this proof for
+    /// `txid`.
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if `txid` or a branch hash is not 32 bytes
+    /// of hex, or if `pos` does not fit a tree of the branch's depth.
+    pub fn merkle_root(&self, txid: &str) -> Result<String, Error> {
+        let depth = self.merkle.len();
+        if depth < 64 && self.pos >> depth != 0 {
+            return Err(Error::Decode(format!(
+                "merkle proof position {} out of range for a branch of {} hashes",
+                self.pos, depth
+            )));
+        }
+        let mut node = decode_display_hash(txid, "txid")?;
+        let mut pos = self.pos;
+        for sibling in &self.merkle {
+            let sibling = decode_display_hash(sibling, "merkle branch hash")?;
+            let mut pair = [0u8; 64];
+            if pos & 1 == 1 {
+                pair[..32].copy_from_slice(&sibling);
+                pair[32..].copy_from_slice(&node);
+            } else {
+                pair[..32].copy_from_slice(&node);
+                pair[32..].copy_from_slice(&sibling);
+            }
+            node = sha256d(&pair);
+            pos >>= 1;
+        }
+        Ok(display_hash(&node))
+    }
+
+    /// Whether this proof places `txid` under `merkle_root` (display hex, as
+    /// in [`Block::merkle_root`]).
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if the proof or `txid` is malformed (see
+    /// [`Self::merkle_root`]).
+    pub fn verify(&self, txid: &str, merkle_root: &str) -> Result<bool, Error> {
+        Ok(self
+            .merkle_root(txid)?
+            .eq_ignore_ascii_case(merkle_root.trim()))
+    }
+
+    /// Whether this proof places `txid` in `block`: the rebuilt root must
+    /// match [`Block::merkle_root`], the heights must agree, and `pos` and the
+    /// branch length must fit a tree of [`Block::tx_count`] leaves. The last
+    /// check stops a proof for an inner node (64 bytes that happen to parse
+    /// as a transaction) from passing as a leaf.
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if the proof or `txid` is malformed.
+    pub fn verify_block(&self, txid: &str, block: &Block) -> Result<bool, Error> {
+        let depth = 64 - u64::leading_zeros(block.tx_count.saturating_sub(1)) as usize;
+        if block.height != self.block_height
+            || self.pos >= block.tx_count
+            || self.merkle.len() != depth
+        {
+            return Ok(false);
+        }
+        self.verify(txid, &block.merkle_root)
+    }
+
+    /// Whether this proof places `txid` in the block with this 80-byte header
+    /// (hex, as returned by [`crate::Client::get_block_header`]).
+    ///
+    /// # Errors
+    /// Returns [`Error::Decode`] if the header is not 80 bytes of hex, or the
+    /// proof or `txid` is malformed.
+    pub fn verify_header(&self, txid: &str, header_hex: &str) -> Result<bool, Error> {
+        let header = BlockHeader::from_hex(header_hex)?;
+        self.verify(txid, &header.merkle_root())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Mainnet block 100000: four transactions.
+    const ROOT: &str = "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766";
+    const HEADER: &str = "0100000050120119172a610421a6c3011dd330d9df07b63616c2cc1f1cd00200000000006657a9252aacd5c0b2940996ecff952228c3067cc38d4885efb5a4ac4247e9f337221b4d4c86041b0f2b5710";
+    const TXID: &str = "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4";
+
+    fn proof() -> MerkleProof {
+        MerkleProof {
+            block_height: 100_000,
+            merkle: vec![
+                "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d".into(),
+                "ccdafb73d8dcd0173d5d5c3c9a0770d0b3953db889dab99ef05b1907518cb815".into(),
+            ],
+            pos: 2,
+        }
+    }
+
+    #[test]
+    fn rebuilds_block_100000_root() {
+        assert_eq!(proof().merkle_root(TXID).unwrap(), ROOT);
+        assert!(proof().verify(TXID, ROOT).unwrap());
+        assert!(proof().verify_header(TXID, HEADER).unwrap());
+    }
+
+    fn block(tx_count: u64) -> Block {
+        Block {
+            id: "000000000003ba27aa200b1cecaad478d2b00432346c3f1f3986da1afd33e506".into(),
+            height: 100_000,
+            version: 1,
+            timestamp: 1_293_623_863,
+            tx_count,
+            size: 957,
+            weight: 3828,
+            merkle_root: ROOT.into(),
+            previousblockhash: Some(
+                "000000000002d01c1fccc21636b607dfd930d31d01c3a62104612a1719011250".into(),
+            ),
+            nonce: 274_148_111,
+            bits: 453_281_356,
+        }
+    }
+
+    #[test]
+    fn checks_proof_shape_against_block() {
+        assert!(proof().verify_block(TXID, &block(4)).unwrap());
+        // Same root, but a 2-leaf tree has no position 2, and a 5-leaf tree
+        // is three levels deep.
+        assert!(!proof().verify_block(TXID, &block(2)).unwrap());
+        assert!(!proof().verify_block(TXID, &block(5)).unwrap());
+
+        let mut past_end = proof();
+        past_end.pos = 3;
+        assert!(!past_end.verify_block(TXID, &block(3)).unwrap());
+
+        let mut wrong_height = block(4);
+        wrong_height.height = 99_999;
+        assert!(!proof().verify_block(TXID, &wrong_height).unwrap());
+    }
+
+    #[test]
+    fn rejects_wrong_txid_or_position() {
+        let other = "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87";
+        assert!(!proof().verify(other, ROOT).unwrap());
+
+        let mut swapped = proof();
+        swapped.pos = 3;
+        assert!(!swapped.verify(TXID, ROOT).unwrap());
+
+        let mut out_of_range = proof();
+        out_of_range.pos = 4;
+        assert!(matches!(
+            out_of_range.merkle_root(TXID),
+            Err(Error::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_malformed_header() {
+        assert!(matches!(
+            proof().verify_header(TXID, &HEADER[..150]),
+            Err(Error::Decode(_))
+        ));
+    }
+}
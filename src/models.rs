@@ -142,6 +142,19 @@ pub struct Outspend {
     pub status: Option<TxStatus>,
 }
 
+/// A transaction's merkle inclusion proof, from `tx/:txid/merkle-proof`.
+/// Check it with [`MerkleProof::verify`](crate::MerkleProof::verify) and
+/// friends.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MerkleProof {
+    /// The height of the block containing the transaction.
+    pub block_height: u64,
+    /// The merkle branch: sibling hashes (display hex) from the leaf upwards.
+    pub merkle: Vec<String>,
+    /// The transaction's index in the block.
+    pub pos: u64,
+}
+
 /// Represents statistics for an address.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Stats {
@@ -337,3 +350,109 @@ pub struct WaterfallResponse {
     /// The chain tip block hash (hex) at scan time.
     pub tip: String,
 }
+
+/// The result of a package broadcast (`POST txs/package`), as returned by
+/// bitcoind's `submitpackage`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackageResult {
+    /// `"success"` if every transaction was accepted (or already known);
+    /// otherwise the reason the package as a whole failed.
+    pub package_msg: String,
+    /// Per-transaction results, keyed by wtxid.
+    #[serde(rename = "tx-results", default)]
+    pub tx_results: std::collections::HashMap<String, PackageTxResult>,
+    /// Txids of mempool transactions the package replaced.
+    #[serde(rename = "replaced-transactions", default)]
+    pub replaced_transactions: Vec<String>,
+}
+
+impl PackageResult {
+    /// Whether the whole package was accepted.
+    pub fn is_success(&self) -> bool {
+        self.package_msg == "success"
+    }
+}
+
+/// One transaction's entry in a [`PackageResult`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackageTxResult {
+    /// The transaction ID.
+    pub txid: String,
+    /// Set when a transaction with the same txid but a different witness
+    /// was already in the mempool and was used instead.
+    #[serde(rename = "other-wtxid", default)]
+    pub other_wtxid: Option<String>,
+    /// The virtual size, in vbytes.
+    #[serde(default)]
+    pub vsize: Option<u64>,
+    /// Fee information; absent if the transaction was not accepted.
+    #[serde(default)]
+    pub fees: Option<PackageFees>,
+    /// Why this transaction was rejected, if it was.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Fees of an accepted [`PackageTxResult`] or [`MempoolAcceptResult`].
+/// Amounts are in BTC, as bitcoind reports them.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PackageFees {
+    /// The transaction's own fee, in BTC.
+    pub base: f64,
+    /// The feerate the transaction was evaluated at, in BTC/kvB: for a
+    /// child paying for its parent, the package feerate.
+    #[serde(rename = "effective-feerate", default)]
+    pub effective_feerate: Option<f64>,
+    /// The wtxids whose fees and vsizes make up `effective_feerate`.
+    #[serde(rename = "effective-includes", default)]
+    pub effective_includes: Vec<String>,
+}
+
+impl PackageFees {
+    /// [`Self::effective_feerate`] in sat/vB.
+    pub fn effective_feerate_sat_vb(&self) -> Option<f64> {
+        self.effective_feerate.map(|btc_kvb| btc_kvb * 100_000.0)
+    }
+}
+
+/// One transaction's verdict from `POST txs/test` (bitcoind's
+/// `testmempoolaccept`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct MempoolAcceptResult {
+    /// The transaction ID.
+    pub txid: String,
+    /// The witness transaction ID.
+    #[serde(default)]
+    pub wtxid: Option<String>,
+    /// Whether the transaction would be accepted into the mempool.
+    #[serde(default)]
+    pub allowed: bool,
+    /// The virtual size, in vbytes (only when allowed).
+    #[serde(default)]
+    pub vsize: Option<u64>,
+    /// Fee information (only when allowed).
+    #[serde(default)]
+    pub fees: Option<PackageFees>,
+    /// Why the transaction would be rejected, e.g. `min relay fee not met`.
+    #[serde(rename = "reject-reason", default)]
+    pub reject_reason: Option<String>,
+    /// Why a multi-transaction package was rejected as a whole.
+    #[serde(rename = "package-error", default)]
+    pub package_error: Option<String>,
+}
+
+impl MempoolAcceptResult {
+    /// The category of the rejection — the same one a failed broadcast of
+    /// this transaction would report — or `None` if it was allowed.
+    pub fn rejection(&self) -> Option<crate::BroadcastRejection> {
+        if self.allowed {
+            return None;
+        }
+        let reason = self
+            .reject_reason
+            .as_deref()
+            .or(self.package_error.as_deref())
+            .unwrap_or_default();
+        Some(crate::BroadcastRejection::from_reason(reason))
+    }
+}
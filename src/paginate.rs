@@ -0,0 +1,279 @@
+//! Lazy, auto-paginating streams over Esplora's cursor-paged endpoints.
+//!
+//! Esplora pages several listings with a cursor in the path, and each uses a
+//! different one:
+//!
+//! | Stream | Endpoint | Cursor |
+//! |---|---|---|
+//! | [`Client::address_txs_stream`] | `address/:address/txs/chain/:last_seen` | last txid, 25 per page |
+//! | [`Client::scripthash_txs_stream`] | `scripthash/:hash/txs/chain/:last_seen` | last txid, 25 per page |
+//! | [`Client::asset_txs_stream`] | `asset/:asset_id/txs/chain/:last_seen` | last txid, 25 per page |
+//! | [`Client::block_txs_stream`] | `block/:hash/txs/:start_index` | index, steps of 25 |
+//! | [`Client::blocks_stream`] | `blocks/:start_height` | height, steps of 10 downwards |
+//!
+//! The streams here do that cursor arithmetic for the caller: a page is only
+//! requested once the previous one has been consumed, and the stream ends at
+//! the end of the data or after the first error.
+//!
+//! Every stream is a [`Paged`], which can be consumed with the usual
+//! [`futures::StreamExt`] combinators or drained with [`Paged::collect_all`].
+
+use std::collections::HashSet;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+
+use crate::{Block, Client, Error, Transaction};
+
+/// Confirmed transactions per page of the `address`, `scripthash` and `asset`
+/// `txs/chain` endpoints.
+pub const CHAIN_TXS_PAGE_SIZE: usize = 25;
+
+/// Transactions per page of `block/:hash/txs/:start_index`.
+pub const BLOCK_TXS_PAGE_SIZE: u64 = 25;
+
+/// Blocks per page of `blocks/:start_height`.
+pub const BLOCKS_PAGE_SIZE: u64 = 10;
+
+/// A lazily paginated stream of `T`, yielding one item at a time and ending
+/// after the last page or the first error.
+///
+/// It owns a clone of the [`Client`], so it can outlive the borrow it was
+/// created from and be moved into a spawned task.
+pub struct Paged<T> {
+    inner: BoxStream<'static, Result<T, Error>>,
+}
+
+impl<T> fmt::Debug for Paged<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Paged").finish_non_exhaustive()
+    }
+}
+
+impl<T> Stream for Paged<T> {
+    type Item = Result<T, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl<T: Send + 'static> Paged<T> {
+    /// Pages through `fetch`, starting at `first`. Each call returns a page
+    /// and the cursor for the next one, or `None` when that page was the last.
+    pub(crate) fn new<C, F, Fut>(first: C, mut fetch: F) -> Self
+    where
+        C: Send + 'static,
+        F: FnMut(C) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(Vec<T>, Option<C>), Error>> + Send + 'static,
+    {
+        let pages = stream::try_unfold(Some(first), move |cursor| {
+            let page = cursor.map(&mut fetch);
+            async move {
+                match page {
+                    Some(page) => page.await.map(Some),
+                    None => Ok(None),
+                }
+            }
+        });
+        Self {
+            inner: pages
+                .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+                .try_flatten()
+                .boxed(),
+        }
+    }
+
+    /// Drains the stream into a `Vec`, stopping early once `cap` items have
+    /// been collected (no further pages are requested).
+    ///
+    /// # Errors
+    /// Returns the first [`Error`] the stream yields; items collected before
+    /// it are discarded.
+    pub async fn collect_all(mut self, cap: Option<usize>) -> Result<Vec<T>, Error> {
+        let cap = cap.unwrap_or(usize::MAX);
+        let mut items = Vec::new();
+        while items.len() < cap {
+            match self.next().await {
+                Some(item) => items.push(item?),
+                None => break,
+            }
+        }
+        Ok(items)
+    }
+}
+
+impl Client {
+    /// Streams an address's full transaction history: its mempool
+    /// transactions first, then its confirmed ones, newest first, paged
+    /// [`CHAIN_TXS_PAGE_SIZE`] at a time via
+    /// [`Self::get_address_txs_chain`].
+    ///
+    /// A mempool transaction that confirms while the history is being walked
+    /// is only yielded once.
+    ///
+    /// ```no_run
+    /// # async fn demo(client: esplora_rs::Client) -> Result<(), esplora_rs::Error> {
+    /// use futures::StreamExt;
+    ///
+    /// let mut txs = client.address_txs_stream("tb1q...");
+    /// while let Some(tx) = txs.next().await {
+    ///     println!("{}", tx?.txid);
+    /// }
+    ///
+    /// // Or at most 100 of them in one go:
+    /// let recent = client.address_txs_stream("tb1q...").collect_all(Some(100)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn address_txs_stream(&self, address: &str) -> Paged<Transaction> {
+        self.history_stream(History::Address, address)
+    }
+
+    /// Streams a scripthash's full transaction history, like
+    /// [`Self::address_txs_stream`], via [`Self::get_scripthash_txs_chain`].
+    pub fn scripthash_txs_stream(&self, hash: &str) -> Paged<Transaction> {
+        self.history_stream(History::Scripthash, hash)
+    }
+
+    /// Streams an asset's full transaction history (Elements/Liquid): its
+    /// mempool transactions first, then its confirmed ones, newest first,
+    /// paged [`CHAIN_TXS_PAGE_SIZE`] at a time via
+    /// [`Self::get_asset_txs_chain`].
+    pub fn asset_txs_stream(&self, asset_id: &str) -> Paged<Transaction> {
+        self.history_stream(History::Asset, asset_id)
+    }
+
+    /// Streams every transaction in a block, in block order, paged
+    /// [`BLOCK_TXS_PAGE_SIZE`] at a time via [`Self::get_block_txs`].
+    ///
+    /// The block itself is fetched first for its `tx_count`, so the stream
+    /// stops exactly at the last page rather than probing past it.
+    pub fn block_txs_stream(&self, hash: &str) -> Paged<Transaction> {
+        let client = self.clone();
+        let hash = hash.to_string();
+        Paged::new(None, move |cursor: Option<(u64, u64)>| {
+            let client = client.clone();
+            let hash = hash.clone();
+            async move {
+                let (start, tx_count) = match cursor {
+                    Some(cursor) => cursor,
+                    None => (0, client.get_block(&hash).await?.tx_count),
+                };
+                if start >= tx_count {
+                    return Ok((Vec::new(), None));
+                }
+                let txs = client.get_block_txs(&hash, Some(start)).await?;
+                let next = start + BLOCK_TXS_PAGE_SIZE;
+                let more = next < tx_count && !txs.is_empty();
+                Ok((txs, more.then_some(Some((next, tx_count)))))
+            }
+        })
+    }
+
+    /// Streams blocks from `start_height` (or the tip, if `None`) down to
+    /// genesis, paged [`BLOCKS_PAGE_SIZE`] at a time via [`Self::get_blocks`].
+    ///
+    /// ```no_run
+    /// # async fn demo(client: esplora_rs::Client) -> Result<(), esplora_rs::Error> {
+    /// // The last 50 blocks, newest first.
+    /// let blocks = client.blocks_stream(None).collect_all(Some(50)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn blocks_stream(&self, start_height: Option<u64>) -> Paged<Block> {
+        let client = self.clone();
+        Paged::new(start_height, move |height| {
+            let client = client.clone();
+            async move {
+                let blocks = client.get_blocks(height).await?;
+                let next = match blocks.last() {
+                    Some(lowest) if lowest.height > 0 => Some(Some(lowest.height - 1)),
+                    _ => None,
+                };
+                Ok((blocks, next))
+            }
+        })
+    }
+
+    fn history_stream(&self, history: History, id: &str) -> Paged<Transaction> {
+        let client = self.clone();
+        let id = id.to_string();
+        Paged::new(HistoryCursor::Mempool, move |cursor| {
+            let client = client.clone();
+            let id = id.clone();
+            async move {
+                match cursor {
+                    HistoryCursor::Mempool => {
+                        let txs = history.mempool(&client, &id).await?;
+                        let mempool = txs.iter().map(|tx| tx.txid.clone()).collect();
+                        let next = HistoryCursor::Chain {
+                            last_seen: None,
+                            mempool,
+                        };
+                        Ok((txs, Some(next)))
+                    }
+                    HistoryCursor::Chain { last_seen, mempool } => {
+                        let mut txs = history.chain(&client, &id, last_seen.as_deref()).await?;
+                        let next = match txs.last() {
+                            Some(last) if txs.len() >= CHAIN_TXS_PAGE_SIZE => {
+                                Some(HistoryCursor::Chain {
+                                    last_seen: Some(last.txid.clone()),
+                                    mempool: mempool.clone(),
+                                })
+                            }
+                            _ => None,
+                        };
+                        txs.retain(|tx| !mempool.contains(&tx.txid));
+                        Ok((txs, next))
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Whose transaction history [`Client::history_stream`] walks.
+#[derive(Clone, Copy)]
+enum History {
+    Address,
+    Scripthash,
+    Asset,
+}
+
+impl History {
+    async fn mempool(self, client: &Client, id: &str) -> Result<Vec<Transaction>, Error> {
+        match self {
+            Self::Address => client.get_address_mempool_txs(id).await,
+            Self::Scripthash => client.get_scripthash_mempool_txs(id).await,
+            Self::Asset => client.get_asset_mempool_txs(id).await,
+        }
+    }
+
+    async fn chain(
+        self,
+        client: &Client,
+        id: &str,
+        last_seen: Option<&str>,
+    ) -> Result<Vec<Transaction>, Error> {
+        match self {
+            Self::Address => client.get_address_txs_chain(id, last_seen).await,
+            Self::Scripthash => client.get_scripthash_txs_chain(id, last_seen).await,
+            Self::Asset => client.get_asset_txs_chain(id, last_seen).await,
+        }
+    }
+}
+
+/// Where [`Client::history_stream`] is up to.
+enum HistoryCursor {
+    Mempool,
+    Chain {
+        last_seen: Option<String>,
+        /// Txids already yielded from the mempool, skipped if they confirm
+        /// mid-walk.
+        mempool: HashSet<String>,
+    },
+}
@@ -0,0 +1,208 @@
+//! Client-side request pacing with a token bucket.
+//!
+//! Public instances throttle aggressively (`blockstream.info` signet answers
+//! `429` after ~10 back-to-back requests), so a [`RateLimit`] lets a client
+//! pace itself instead. The bucket is shared by every clone of a
+//! [`crate::Client`], and every request attempt — including retries and
+//! failover hops — takes a token.
+//!
+//! The limiter also adapts: each [`crate::Error::RateLimited`] response halves
+//! the refill rate (down to an eighth of the configured one) and empties the
+//! bucket, honoring the server's `Retry-After` when present — up to the
+//! client's [`crate::RetryPolicy::max_delay`], so a huge value can't freeze
+//! the client. Each successful response then wins back 5% of the configured
+//! rate until it is restored.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::Error;
+
+/// The lowest the refill rate drops to after `429`s, as a fraction of
+/// [`RateLimit::requests_per_second`].
+const MIN_RATE_FRACTION: f64 = 0.125;
+
+/// How much of the configured rate each success restores after tightening.
+const RECOVERY_FRACTION: f64 = 0.05;
+
+/// Token-bucket parameters. Attach with [`crate::ClientBuilder::rate_limit`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    /// Sustained requests per second (the refill rate). Must be finite and
+    /// positive; [`crate::ClientBuilder::build`] rejects anything else.
+    pub requests_per_second: f64,
+    /// Requests that may be sent back-to-back before pacing kicks in (the
+    /// bucket size). At least 1.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// `requests_per_second` sustained, with bursts of up to `burst`.
+    pub fn new(requests_per_second: f64, burst: u32) -> Self {
+        Self {
+            requests_per_second,
+            burst,
+        }
+    }
+
+    /// Rejects a rate that isn't finite and positive, which the bucket
+    /// couldn't refill at.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.requests_per_second.is_finite() && self.requests_per_second > 0.0 {
+            Ok(())
+        } else {
+            Err(Error::Config(format!(
+                "rate limit must be a positive number of requests per second, got {}",
+                self.requests_per_second
+            )))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    rate: f64,
+    refilled_at: Instant,
+    blocked_until: Option<Instant>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    config: RateLimit,
+    /// The longest a `Retry-After` may hold requests back.
+    max_hold: Duration,
+    bucket: Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    /// A limiter for a validated `config` (see [`RateLimit::validate`]) that
+    /// honors `Retry-After` for at most `max_hold`.
+    pub(crate) fn new(config: RateLimit, max_hold: Duration) -> Self {
+        let config = RateLimit {
+            burst: config.burst.max(1),
+            ..config
+        };
+        Self {
+            config,
+            max_hold,
+            bucket: Mutex::new(Bucket {
+                tokens: f64::from(config.burst),
+                rate: config.requests_per_second,
+                refilled_at: Instant::now(),
+                blocked_until: None,
+            }),
+        }
+    }
+
+    /// The current refill rate, which is below the configured one while the
+    /// limiter is backing off from `429`s.
+    pub(crate) fn current_rate(&self) -> f64 {
+        self.bucket.lock().expect("rate limiter lock poisoned").rate
+    }
+
+    /// Waits until a token is available and takes it.
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+                let now = Instant::now();
+                match bucket.blocked_until {
+                    Some(until) if until > now => until - now,
+                    _ => {
+                        bucket.blocked_until = None;
+                        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                        bucket.tokens = (bucket.tokens + elapsed * bucket.rate)
+                            .min(f64::from(self.config.burst));
+                        bucket.refilled_at = now;
+                        if bucket.tokens >= 1.0 {
+                            bucket.tokens -= 1.0;
+                            return;
+                        }
+                        Duration::from_secs_f64((1.0 - bucket.tokens) / bucket.rate)
+                    }
+                }
+            };
+            debug!(target: "esplora_rs", "rate limiter: waiting {:?}", wait);
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Tightens after a `429`: halves the rate, drains the bucket, and holds
+    /// every request until `retry_after` seconds (at most `max_hold`) have
+    /// passed.
+    pub(crate) fn on_rate_limited(&self, retry_after: Option<u64>) {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        let floor = self.config.requests_per_second * MIN_RATE_FRACTION;
+        bucket.rate = (bucket.rate / 2.0).max(floor);
+        bucket.tokens = 0.0;
+        bucket.refilled_at = Instant::now();
+        if let Some(secs) = retry_after {
+            let hold = Duration::from_secs(secs).min(self.max_hold);
+            bucket.blocked_until = Instant::now().checked_add(hold);
+        }
+        warn!(target: "esplora_rs", "rate limited by server; pacing at {:.2} req/s", bucket.rate);
+    }
+
+    /// Loosens after a success, back towards the configured rate.
+    pub(crate) fn on_success(&self) {
+        let mut bucket = self.bucket.lock().expect("rate limiter lock poisoned");
+        if bucket.rate < self.config.requests_per_second {
+            bucket.rate = (bucket.rate + self.config.requests_per_second * RECOVERY_FRACTION)
+                .min(self.config.requests_per_second);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limited_halves_down_to_floor_and_success_recovers() {
+        let limiter = RateLimiter::new(RateLimit::new(8.0, 4), Duration::from_secs(30));
+        limiter.on_rate_limited(None);
+        assert_eq!(limiter.current_rate(), 4.0);
+        for _ in 0..10 {
+            limiter.on_rate_limited(None);
+        }
+        assert_eq!(limiter.current_rate(), 1.0);
+        for _ in 0..100 {
+            limiter.on_success();
+        }
+        assert_eq!(limiter.current_rate(), 8.0);
+    }
+
+    #[test]
+    fn retry_after_hold_is_capped() {
+        let limiter = RateLimiter::new(RateLimit::new(8.0, 4), Duration::from_secs(30));
+        limiter.on_rate_limited(Some(u64::MAX));
+        let until = limiter.bucket.lock().unwrap().blocked_until.unwrap();
+        assert!(until <= Instant::now() + Duration::from_secs(30));
+    }
+
+    #[test]
+    fn rejects_rates_the_bucket_cannot_refill_at() {
+        for rate in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(RateLimit::new(rate, 1).validate().is_err(), "{rate}");
+        }
+        assert!(RateLimit::new(0.5, 1).validate().is_ok());
+    }
+
+    #[tokio::test]
+    async fn burst_is_free_then_paced() {
+        let limiter = RateLimiter::new(RateLimit::new(20.0, 2), Duration::from_secs(30));
+        let start = Instant::now();
+        for _ in 0..2 {
+            limiter.acquire().await;
+        }
+        assert!(start.elapsed() < Duration::from_millis(40));
+        for _ in 0..2 {
+            limiter.acquire().await;
+        }
+        // Two more tokens at 20/s take ~100 ms.
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+}
@@ -0,0 +1,180 @@
+//! Just enough raw-transaction parsing to compute a txid and list the spent
+//! outpoints locally, for [`crate::Client::broadcast_and_confirm`].
+//!
+//! The txid is the double SHA-256 of the legacy serialization, so a segwit
+//! transaction has its marker, flag and witnesses stripped first. Only the
+//! Bitcoin format is understood; Elements/Liquid transactions fail to parse.
+
+use crate::broadcast::OutPoint;
+use crate::hashes::{decode_hex, display_hash, sha256d};
+use crate::Error;
+
+/// The parts of a raw transaction the client needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RawTx {
+    /// The txid, as display hex.
+    pub(crate) txid: String,
+    /// The outpoints the inputs spend, in input order.
+    pub(crate) inputs: Vec<OutPoint>,
+}
+
+impl RawTx {
+    /// Parses a transaction from hex.
+    pub(crate) fn from_hex(hex: &str) -> Result<Self, Error> {
+        let bytes = decode_hex(hex, "transaction")?;
+        let mut r = Reader {
+            bytes: &bytes,
+            pos: 0,
+        };
+
+        r.take(4)?; // version
+        let segwit = r.peek(2) == Some(&[0x00, 0x01][..]);
+        if segwit {
+            r.take(2)?;
+        }
+        let body_start = r.pos;
+        let input_count = r.varint()?;
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let prev: [u8; 32] = r.take(32)?.try_into().expect("32 bytes");
+            let vout = u32::from_le_bytes(r.take(4)?.try_into().expect("4 bytes"));
+            let script_len = r.varint()?;
+            r.take(script_len)?;
+            r.take(4)?; // sequence
+            inputs.push(OutPoint {
+                txid: display_hash(&prev),
+                vout,
+            });
+        }
+        let output_count = r.varint()?;
+        for _ in 0..output_count {
+            r.take(8)?; // value
+            let script_len = r.varint()?;
+            r.take(script_len)?;
+        }
+        let body_end = r.pos;
+        if segwit {
+            for _ in 0..input_count {
+                for _ in 0..r.varint()? {
+                    let item_len = r.varint()?;
+                    r.take(item_len)?;
+                }
+            }
+        }
+        r.take(4)?; // lock time
+        if r.pos != bytes.len() {
+            return Err(Error::Decode(format!(
+                "transaction has {} trailing bytes",
+                bytes.len() - r.pos
+            )));
+        }
+
+        let mut legacy = Vec::with_capacity(bytes.len());
+        legacy.extend_from_slice(&bytes[..4]);
+        legacy.extend_from_slice(&bytes[body_start..body_end]);
+        legacy.extend_from_slice(&bytes[bytes.len() - 4..]);
+        Ok(Self {
+            txid: display_hash(&sha256d(&legacy)),
+            inputs,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self, len: usize) -> Option<&'a [u8]> {
+        self.bytes.get(self.pos..self.pos.checked_add(len)?)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let slice = self.peek(len).ok_or_else(|| {
+            Error::Decode(format!(
+                "transaction truncated at byte {} (wanted {len} more)",
+                self.pos
+            ))
+        })?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    /// A Bitcoin `CompactSize` length.
+    fn varint(&mut self) -> Result<usize, Error> {
+        let n = match self.take(1)?[0] {
+            0xfd => u64::from(u16::from_le_bytes(
+                self.take(2)?.try_into().expect("2 bytes"),
+            )),
+            0xfe => u64::from(u32::from_le_bytes(
+                self.take(4)?.try_into().expect("4 bytes"),
+            )),
+            0xff => u64::from_le_bytes(self.take(8)?.try_into().expect("8 bytes")),
+            n => u64::from(n),
+        };
+        // Anything longer than the transaction itself is garbage; rejecting it
+        // here also keeps the loops above from spinning on a huge count.
+        usize::try_from(n)
+            .ok()
+            .filter(|&n| n <= self.bytes.len())
+            .ok_or_else(|| Error::Decode(format!("transaction length field {n} out of range")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The genesis block's coinbase.
+    const GENESIS_COINBASE: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+
+    #[test]
+    fn legacy_txid_and_inputs() {
+        let tx = RawTx::from_hex(GENESIS_COINBASE).unwrap();
+        assert_eq!(
+            tx.txid,
+            "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b"
+        );
+        assert_eq!(
+            tx.inputs,
+            [OutPoint {
+                txid: "0".repeat(64),
+                vout: 0xffff_ffff
+            }]
+        );
+    }
+
+    #[test]
+    fn segwit_txid_ignores_witness() {
+        // One input spending aa..aa:1 and one P2WPKH output, serialized
+        // without and then with a marker, flag and two-item witness.
+        let input = format!("{}01000000{}", "aa".repeat(32), "00ffffffff");
+        let output = format!("e803000000000000160014{}", "bb".repeat(20));
+        let witness = format!("0247{}21{}", "cc".repeat(0x47), "dd".repeat(0x21));
+        let legacy_hex = format!("0200000001{input}01{output}00000000");
+        let segwit_hex = format!("02000000000101{input}01{output}{witness}00000000");
+
+        let legacy = RawTx::from_hex(&legacy_hex).unwrap();
+        assert_eq!(
+            legacy.txid,
+            display_hash(&sha256d(&decode_hex(&legacy_hex, "tx").unwrap()))
+        );
+        assert_eq!(
+            legacy.inputs,
+            [OutPoint {
+                txid: "aa".repeat(32),
+                vout: 1
+            }]
+        );
+        assert_eq!(RawTx::from_hex(&segwit_hex).unwrap(), legacy);
+    }
+
+    #[test]
+    fn rejects_truncated_or_padded() {
+        let short = &GENESIS_COINBASE[..GENESIS_COINBASE.len() - 2];
+        assert!(matches!(RawTx::from_hex(short), Err(Error::Decode(_))));
+        let long = format!("{GENESIS_COINBASE}00");
+        assert!(matches!(RawTx::from_hex(&long), Err(Error::Decode(_))));
+    }
+}
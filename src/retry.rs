@@ -0,0 +1,173 @@
+//! [`RetryPolicy`]: bounded retry with jittered exponential backoff.
+//!
+//! Applied by the idempotent request helpers (every `GET`). `broadcast_tx` is
+//! only retried when the policy opts in via [`RetryPolicy::retry_broadcast`],
+//! since re-posting a transaction after an ambiguous failure is the caller's
+//! call to make.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How failed requests are retried. Attach one with
+/// [`crate::ClientBuilder::retry`]; clients retry nothing by default.
+///
+/// A failure is retried when [`crate::Error::is_retryable`] holds (`429`,
+/// `5xx`, connect errors and timeouts) and attempts remain. The wait before
+/// attempt `n + 1` is the server's `Retry-After` when it sent one, otherwise
+/// `base_delay * 2^(n-1)` capped at `max_delay`, with jitter applied. A
+/// `Retry-After` longer than `max_delay` ends the retries instead of
+/// sleeping through it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first. `1` disables retries.
+    pub max_attempts: u32,
+    /// Backoff before the second attempt; doubled for each later one.
+    pub base_delay: Duration,
+    /// Upper bound on any single backoff (and on an honored `Retry-After`).
+    pub max_delay: Duration,
+    /// Randomize each computed backoff to between half and all of its value,
+    /// so clients sharing a throttled endpoint don't retry in lockstep.
+    pub jitter: bool,
+    /// Also retry `broadcast_tx`. Off by default: a timed-out broadcast may
+    /// have been accepted, and re-posting it is not always what the caller
+    /// wants.
+    pub retry_broadcast: bool,
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, 500 ms base backoff, 30 s cap, jitter on, broadcasts
+    /// not retried.
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_broadcast: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt. This is what a [`crate::Client`]
+    /// uses unless configured otherwise.
+    #[must_use]
+    pub fn disabled() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// The default policy with `max_attempts` total attempts.
+    #[must_use]
+    pub fn with_max_attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the base and maximum backoff.
+    #[must_use]
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Enables or disables jitter.
+    #[must_use]
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Opts `broadcast_tx` into retries.
+    #[must_use]
+    pub fn retry_broadcast(mut self, retry_broadcast: bool) -> Self {
+        self.retry_broadcast = retry_broadcast;
+        self
+    }
+
+    /// The wait before the attempt following failed attempt `attempt`
+    /// (1-based), or `None` if the server asked for longer than `max_delay`.
+    pub(crate) fn delay_for(&self, attempt: u32, retry_after: Option<u64>) -> Option<Duration> {
+        if let Some(secs) = retry_after {
+            let wait = Duration::from_secs(secs);
+            return (wait <= self.max_delay).then_some(wait);
+        }
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        if self.jitter {
+            Some(exp / 2 + exp.mul_f64(random_fraction() / 2.0))
+        } else {
+            Some(exp)
+        }
+    }
+}
+
+/// One failed attempt, kept for [`crate::Error::RetriesExhausted`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryAttempt {
+    /// The attempt number, starting at 1.
+    pub attempt: u32,
+    /// The HTTP status, when the attempt got a response.
+    pub status: Option<u16>,
+    /// The attempt's error, rendered.
+    pub error: String,
+    /// How long the client waited before the next attempt; `None` for the
+    /// final one.
+    pub delay: Option<Duration>,
+}
+
+/// A uniform value in `[0, 1)`. Jitter needs spread, not cryptographic
+/// randomness, so this avoids a `rand` dependency: `RandomState` is seeded
+/// per instance, and the clock is mixed in for good measure.
+fn random_fraction() -> f64 {
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    hasher.write_u128(nanos);
+    // Top 53 bits → an exactly representable f64 in [0, 1).
+    #[allow(clippy::cast_precision_loss)]
+    let fraction = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+    fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_and_caps() {
+        let policy = RetryPolicy::default()
+            .backoff(Duration::from_millis(100), Duration::from_millis(350))
+            .jitter(false);
+        assert_eq!(policy.delay_for(1, None), Some(Duration::from_millis(100)));
+        assert_eq!(policy.delay_for(2, None), Some(Duration::from_millis(200)));
+        assert_eq!(policy.delay_for(3, None), Some(Duration::from_millis(350)));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_to_full() {
+        let policy = RetryPolicy::default().backoff(Duration::from_millis(1000), Duration::MAX);
+        for _ in 0..100 {
+            let d = policy.delay_for(1, None).unwrap();
+            assert!(d >= Duration::from_millis(500) && d <= Duration::from_millis(1000));
+        }
+    }
+
+    #[test]
+    fn retry_after_is_honored_up_to_max_delay() {
+        let policy =
+            RetryPolicy::default().backoff(Duration::from_millis(100), Duration::from_secs(10));
+        assert_eq!(policy.delay_for(1, Some(4)), Some(Duration::from_secs(4)));
+        assert_eq!(policy.delay_for(1, Some(0)), Some(Duration::ZERO));
+        assert_eq!(policy.delay_for(1, Some(60)), None);
+    }
+}
@@ -0,0 +1,44 @@
+//! Electrum-style scripthashes, for the `scripthash/:hash/...` endpoints.
+//!
+//! Esplora indexes history by `SHA256(scriptPubKey)`, displayed byte-reversed
+//! (the Electrum protocol convention). That covers scripts with no address
+//! form — bare multisig, `OP_RETURN` outputs, non-standard scripts — and lets
+//! callers that already hold a scriptPubKey skip address encoding.
+
+use crate::hashes::{decode_hex, display_hash, sha256};
+use crate::Error;
+
+/// Computes the scripthash Esplora expects for a scriptPubKey given as hex:
+/// the SHA-256 of the script bytes, byte-reversed, as lowercase hex.
+///
+/// ```
+/// // P2WPKH scriptPubKey → its Esplora/Electrum scripthash.
+/// let hash = esplora_rs::scripthash("0014f6b5212642a8b9e83693e5b382d6a6c561763c0a").unwrap();
+/// assert_eq!(hash.len(), 64);
+/// ```
+///
+/// # Errors
+/// Returns [`Error::Decode`] if `script_pubkey_hex` is not valid hex.
+pub fn scripthash(script_pubkey_hex: &str) -> Result<String, Error> {
+    let script = decode_hex(script_pubkey_hex, "scriptPubKey")?;
+    Ok(display_hash(&sha256(&script)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_electrum_protocol_example() {
+        // The P2PKH example from the Electrum protocol docs ("Script Hashes").
+        assert_eq!(
+            scripthash("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap(),
+            "8b01df4e368ea28f8dc0423bcf7a4923e3a12d307c875e47a0cfbf90b5c39161"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_hex() {
+        assert!(matches!(scripthash("0014zz"), Err(Error::Decode(_))));
+    }
+}
@@ -0,0 +1,150 @@
+//! Coalescing of concurrent identical GETs.
+//!
+//! While a request for a key is in flight, further callers with the same key
+//! wait for its outcome instead of sending their own. The first caller (the
+//! leader) does the work; everyone gets the same body or a copy of the same
+//! error (see [`Error::Shared`]). If the leader is cancelled before it
+//! finishes, one of the waiters takes over.
+//!
+//! Only requests that overlap in time are merged; a finished result is not
+//! remembered (that's what [`crate::cache`] is for).
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use tokio::sync::watch;
+use tracing::trace;
+
+use crate::Error;
+
+type Outcome = Result<Bytes, Arc<Error>>;
+
+/// The requests in flight, shared by every clone of a client.
+#[derive(Debug, Default)]
+pub(crate) struct SingleFlight {
+    calls: Mutex<HashMap<String, watch::Receiver<Option<Outcome>>>>,
+}
+
+enum Role {
+    Lead(watch::Sender<Option<Outcome>>),
+    Wait(watch::Receiver<Option<Outcome>>),
+}
+
+impl SingleFlight {
+    /// Runs `op` for `key`, unless a call for `key` is already running, in
+    /// which case its outcome is returned instead.
+    pub(crate) async fn run<F, Fut>(&self, key: String, op: F) -> Result<Bytes, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Bytes, Error>>,
+    {
+        let tx = loop {
+            let role = {
+                let mut calls = self.calls.lock().expect("single-flight lock poisoned");
+                match calls.get(&key) {
+                    Some(rx) => Role::Wait(rx.clone()),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        calls.insert(key.clone(), rx);
+                        Role::Lead(tx)
+                    }
+                }
+            };
+            match role {
+                Role::Lead(tx) => break tx,
+                Role::Wait(mut rx) => {
+                    trace!(target: "esplora_rs", "joining in-flight request for {}", key);
+                    loop {
+                        if let Some(outcome) = rx.borrow().clone() {
+                            return outcome.map_err(|e| Error::shared(&e));
+                        }
+                        if rx.changed().await.is_err() {
+                            break; // the leader was cancelled; try to lead
+                        }
+                    }
+                }
+            }
+        };
+
+        let guard = Leader {
+            flight: self,
+            key,
+            tx,
+        };
+        let outcome = op().await.map_err(Arc::new);
+        guard.tx.send_replace(Some(outcome.clone()));
+        drop(guard);
+        // With no waiters left holding it, the leader keeps the original
+        // error rather than a copy.
+        outcome.map_err(|e| Arc::try_unwrap(e).unwrap_or_else(|e| Error::shared(&e)))
+    }
+}
+
+/// Unregisters the leader's call when it finishes or is dropped, before its
+/// sender closes, so woken waiters never find the stale entry.
+struct Leader<'a> {
+    flight: &'a SingleFlight,
+    key: String,
+    tx: watch::Sender<Option<Outcome>>,
+}
+
+impl Drop for Leader<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut calls) = self.flight.calls.lock() {
+            calls.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn concurrent_calls_share_one_run() {
+        let flight = SingleFlight::default();
+        let runs = AtomicUsize::new(0);
+        let op = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(Bytes::from_static(b"body"))
+        };
+        let (a, b) = tokio::join!(flight.run("k".into(), op), flight.run("k".into(), op));
+        assert_eq!(
+            (a.unwrap(), b.unwrap()),
+            (Bytes::from("body"), Bytes::from("body"))
+        );
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // Finished calls aren't remembered.
+        flight.run("k".into(), op).await.unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(flight.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn waiter_takes_over_from_cancelled_leader() {
+        let flight = Arc::new(SingleFlight::default());
+        let f = flight.clone();
+        let leader = tokio::spawn(async move {
+            f.run("k".into(), || async {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                Ok(Bytes::new())
+            })
+            .await
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let f = flight.clone();
+        let waiter = tokio::spawn(async move {
+            f.run("k".into(), || async { Ok(Bytes::from_static(b"second")) })
+                .await
+        });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        leader.abort();
+        assert_eq!(waiter.await.unwrap().unwrap(), Bytes::from("second"));
+    }
+}
@@ -0,0 +1,196 @@
+//! [`Client::subscribe_blocks`]: a tip-following stream of block events.
+//!
+//! The subscription polls `blocks/tip/hash` and, whenever the tip moves,
+//! walks back from the new tip along `previousblockhash` until it reaches a
+//! block it already reported. Every block passed on the way is a
+//! [`BlockEvent::Connected`] (so heights skipped between polls are filled
+//! in, oldest first); every previously reported block above the meeting point
+//! is a [`BlockEvent::Disconnected`] (newest first), emitted before the
+//! replacements. A tip that moves back to a reported block with nothing new
+//! on top (a lagging server behind a load balancer) is waited for, not
+//! treated as a reorg.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use tracing::{debug, warn};
+
+use crate::{Block, Client, Error};
+
+/// How many recent blocks a subscription remembers, and so the deepest reorg
+/// it can unwind.
+pub const MAX_REORG_DEPTH: usize = 100;
+
+/// A change to the chain seen by [`Client::subscribe_blocks`].
+#[derive(Debug, Clone)]
+pub enum BlockEvent {
+    /// A block joined the best chain.
+    Connected(Block),
+    /// A previously connected block left the best chain in a reorg.
+    Disconnected(Block),
+}
+
+/// The stream returned by [`Client::subscribe_blocks`]. It never ends on its
+/// own; drop it to stop polling.
+pub struct BlockSubscription {
+    inner: BoxStream<'static, Result<BlockEvent, Error>>,
+}
+
+impl fmt::Debug for BlockSubscription {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockSubscription").finish_non_exhaustive()
+    }
+}
+
+impl Stream for BlockSubscription {
+    type Item = Result<BlockEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+impl Client {
+    /// Follows the chain tip, polling every `poll_interval`.
+    ///
+    /// The first event is `Connected` for the tip at subscription time. A
+    /// failed poll is yielded as an `Err` item and polling carries on. A reorg
+    /// deeper than [`MAX_REORG_DEPTH`] can't be unwound: it is yielded as
+    /// [`Error::InvalidChain`], after which the subscription restarts from
+    /// the new tip.
+    ///
+    /// ```no_run
+    /// # async fn demo(client: esplora_rs::Client) {
+    /// use std::time::Duration;
+    /// use esplora_rs::BlockEvent;
+    /// use futures::StreamExt;
+    ///
+    /// let mut blocks = client.subscribe_blocks(Duration::from_secs(30));
+    /// while let Some(event) = blocks.next().await {
+    ///     match event {
+    ///         Ok(BlockEvent::Connected(b)) => println!("+ {} {}", b.height, b.id),
+    ///         Ok(BlockEvent::Disconnected(b)) => println!("- {} {}", b.height, b.id),
+    ///         Err(e) => eprintln!("poll failed: {e}"),
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn subscribe_blocks(&self, poll_interval: Duration) -> BlockSubscription {
+        let state = Follower {
+            client: self.clone(),
+            poll_interval,
+            polled: false,
+            recent: VecDeque::new(),
+            pending: VecDeque::new(),
+        };
+        let inner = stream::unfold(state, |mut state| async move {
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((item, state));
+                }
+                if state.polled {
+                    tokio::time::sleep(state.poll_interval).await;
+                }
+                state.polled = true;
+                if let Err(err) = state.poll().await {
+                    return Some((Err(err), state));
+                }
+            }
+        });
+        BlockSubscription {
+            inner: inner.boxed(),
+        }
+    }
+}
+
+struct Follower {
+    client: Client,
+    poll_interval: Duration,
+    /// Whether the first (immediate) poll has happened.
+    polled: bool,
+    /// The most recently connected blocks, oldest first.
+    recent: VecDeque<Block>,
+    pending: VecDeque<Result<BlockEvent, Error>>,
+}
+
+impl Follower {
+    /// Checks the tip once and queues any events. Leaves `recent` untouched
+    /// if a request fails part-way.
+    async fn poll(&mut self) -> Result<(), Error> {
+        let tip_hash = self.client.get_tip_hash().await?;
+        if self.recent.back().is_some_and(|b| b.id == tip_hash) {
+            return Ok(());
+        }
+
+        // Walk back from the new tip until we meet a block we've reported.
+        let mut new_blocks = Vec::new();
+        let mut hash = tip_hash;
+        let meeting_point = loop {
+            if let Some(index) = self.recent.iter().position(|b| b.id == hash) {
+                break Some(index);
+            }
+            let block = self.client.get_block(&hash).await?;
+            // At or below our oldest block, the parent can't be one we know.
+            let beyond_window = match self.recent.front() {
+                Some(oldest) => block.height <= oldest.height,
+                None => true,
+            };
+            let parent = block.previousblockhash.clone();
+            new_blocks.push(block);
+            match parent {
+                Some(parent) if !beyond_window => hash = parent,
+                _ => break None,
+            }
+        };
+
+        match meeting_point {
+            Some(index) if new_blocks.is_empty() => {
+                debug!(
+                    target: "esplora_rs",
+                    "server tip {} is behind ours; waiting",
+                    self.recent[index].height
+                );
+            }
+            Some(index) => {
+                if self.recent.len() > index + 1 {
+                    self.client.note_reorg(self.recent[index].height).await;
+                }
+                while self.recent.len() > index + 1 {
+                    let gone = self.recent.pop_back().expect("len checked");
+                    debug!(target: "esplora_rs", "block disconnected: {} {}", gone.height, gone.id);
+                    self.pending.push_back(Ok(BlockEvent::Disconnected(gone)));
+                }
+            }
+            None if self.recent.is_empty() => {
+                // First poll: start from the tip alone.
+                new_blocks.truncate(1);
+            }
+            None => {
+                let message = format!(
+                    "reorg deeper than the {} blocks followed",
+                    self.recent.len()
+                );
+                warn!(target: "esplora_rs", "{message}; restarting from the new tip");
+                let oldest = self.recent.front().expect("not empty").height;
+                self.client.note_reorg(oldest.saturating_sub(1)).await;
+                self.pending.push_back(Err(Error::InvalidChain(message)));
+                self.recent.clear();
+                new_blocks.truncate(1);
+            }
+        }
+
+        for block in new_blocks.into_iter().rev() {
+            debug!(target: "esplora_rs", "block connected: {} {}", block.height, block.id);
+            self.recent.push_back(block.clone());
+            self.pending.push_back(Ok(BlockEvent::Connected(block)));
+        }
+        while self.recent.len() > MAX_REORG_DEPTH {
+            self.recent.pop_front();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,361 @@
+//! Credit and call accounting from response headers.
+//!
+//! Blockstream Enterprise reports the plan's balance in an
+//! `X-Credits-Remaining` header on every response, and QuickSync
+//! (`waterfalls/…`) calls cost [`WATERFALLS_CREDITS`] each. Every client
+//! records each response it receives — retries and failover hops included,
+//! cache hits not — in a [`UsageStats`] shared by all its clones
+//! ([`crate::Client::usage_stats`]):
+//!
+//! - the last balance each backend reported, keyed by its base URL, since
+//!   failover backends may bill different accounts;
+//! - calls per endpoint, keyed by path template (`tx/:txid/status`,
+//!   `address/:address/txs`, `waterfalls/v2/waterfalls`, …);
+//! - an estimate of the credits spent: [`WATERFALLS_CREDITS`] per successful
+//!   QuickSync call and 1 per other successful call. The server's balance is
+//!   the authority; the estimate shows where the credits went.
+//!
+//! A [`CreditAlert`] ([`crate::ClientBuilder::credit_alert`]) logs a warning,
+//! and calls its callback if it has one, when a backend's balance drops below
+//! its threshold. It fires once per crossing and re-arms when that balance is
+//! back at or above the threshold, e.g. after a top-up; each backend crosses
+//! on its own.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use tracing::warn;
+
+/// The response header carrying the plan's remaining credits.
+pub const CREDITS_REMAINING_HEADER: &str = "x-credits-remaining";
+
+/// Credits a QuickSync (`waterfalls/…`) call costs.
+pub const WATERFALLS_CREDITS: u64 = 10;
+
+/// Calls to one endpoint.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EndpointUsage {
+    /// Responses received, whatever their status.
+    pub calls: u64,
+    /// Credits the successful ones are estimated to have cost.
+    pub estimated_credits: u64,
+}
+
+/// A snapshot of a client's usage, from [`crate::Client::usage_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UsageStats {
+    /// The last `X-Credits-Remaining` value each backend sent, keyed by the
+    /// backend's base URL. Backends that never sent one are absent.
+    pub credits_remaining: BTreeMap<String, u64>,
+    /// Responses received, across all endpoints.
+    pub calls: u64,
+    /// Estimated credits spent, across all endpoints.
+    pub estimated_credits: u64,
+    /// Usage per endpoint path template, e.g. `tx/:txid`.
+    pub endpoints: BTreeMap<String, EndpointUsage>,
+}
+
+/// Called with a backend's base URL and its remaining credits.
+type LowCreditCallback = Arc<dyn Fn(&str, u64) + Send + Sync>;
+
+/// Warns when the remaining credits drop below `threshold`. Attach with
+/// [`crate::ClientBuilder::credit_alert`].
+///
+/// ```
+/// use esplora_rs::CreditAlert;
+///
+/// let alert = CreditAlert::new(10_000).on_low(|backend, remaining| {
+///     eprintln!("only {remaining} Esplora credits left at {backend}");
+/// });
+/// ```
+#[derive(Clone)]
+pub struct CreditAlert {
+    /// The balance below which the alert fires.
+    pub threshold: u64,
+    callback: Option<LowCreditCallback>,
+}
+
+impl fmt::Debug for CreditAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CreditAlert")
+            .field("threshold", &self.threshold)
+            .field("callback", &self.callback.is_some())
+            .finish()
+    }
+}
+
+impl CreditAlert {
+    /// An alert that logs a warning below `threshold` credits.
+    pub fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            callback: None,
+        }
+    }
+
+    /// Also calls `callback` with the backend's base URL and its remaining
+    /// credits. It runs on the task that received the response, so it should
+    /// be quick.
+    #[must_use]
+    pub fn on_low(mut self, callback: impl Fn(&str, u64) + Send + Sync + 'static) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    stats: UsageStats,
+    /// Backends the alert has fired for during their current dip below the
+    /// threshold.
+    alerted: BTreeSet<String>,
+}
+
+/// The usage counters shared by a client and its clones.
+#[derive(Debug, Default)]
+pub(crate) struct UsageTracker {
+    alert: Option<CreditAlert>,
+    state: Mutex<State>,
+}
+
+impl UsageTracker {
+    pub(crate) fn new(alert: Option<CreditAlert>) -> Self {
+        Self {
+            alert,
+            state: Mutex::default(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("usage lock poisoned")
+    }
+
+    pub(crate) fn stats(&self) -> UsageStats {
+        self.lock().stats.clone()
+    }
+
+    /// Records a response from `path`, relative to `backend`'s base URL.
+    pub(crate) fn record(
+        &self,
+        (backend, path): (&str, &str),
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) {
+        let endpoint = endpoint(path);
+        let cost = if status.is_success() {
+            estimated_cost(&endpoint)
+        } else {
+            0
+        };
+        let remaining = headers
+            .get(CREDITS_REMAINING_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<u64>().ok());
+
+        let fire = {
+            let mut state = self.lock();
+            let stats = &mut state.stats;
+            stats.calls += 1;
+            stats.estimated_credits += cost;
+            let usage = stats.endpoints.entry(endpoint).or_default();
+            usage.calls += 1;
+            usage.estimated_credits += cost;
+
+            remaining.and_then(|remaining| {
+                (state.stats.credits_remaining).insert(backend.to_string(), remaining);
+                let low = (self.alert.as_ref()).is_some_and(|a| remaining < a.threshold);
+                let fire = if low {
+                    state.alerted.insert(backend.to_string())
+                } else {
+                    state.alerted.remove(backend);
+                    false
+                };
+                fire.then_some(remaining)
+            })
+        };
+
+        if let (Some(remaining), Some(alert)) = (fire, &self.alert) {
+            warn!(
+                target: "esplora_rs",
+                "{} credits remaining at {}, below the alert threshold of {}",
+                remaining, backend, alert.threshold
+            );
+            if let Some(callback) = &alert.callback {
+                callback(backend, remaining);
+            }
+        }
+    }
+}
+
+/// The credits a successful call to `endpoint` is estimated to cost.
+fn estimated_cost(endpoint: &str) -> u64 {
+    if endpoint.starts_with("waterfalls/") {
+        WATERFALLS_CREDITS
+    } else {
+        1
+    }
+}
+
+/// `path` with its parameters replaced by names, so that calls for different
+/// transactions, blocks or addresses count toward the same endpoint.
+fn endpoint(path: &str) -> String {
+    let mut prev = "";
+    path.trim_matches('/')
+        .split('/')
+        .map(|segment| {
+            let is_number = !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit());
+            let name = match prev {
+                "block" => Some(":hash"),
+                "tx" => Some(":txid"),
+                "address" => Some(":address"),
+                "scripthash" => Some(":hash"),
+                "asset" => Some(":asset_id"),
+                "block-height" => Some(":height"),
+                "address-prefix" => Some(":prefix"),
+                "outspend" => Some(":vout"),
+                "txid" => Some(":index"),
+                "chain" => Some(":last_seen_txid"),
+                "blocks" if is_number => Some(":start_height"),
+                "txs" if is_number => Some(":start_index"),
+                _ => None,
+            };
+            prev = segment;
+            name.unwrap_or(segment)
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn endpoints_are_path_templates() {
+        let txid = "ab".repeat(32);
+        let cases = [
+            (format!("tx/{txid}/status"), "tx/:txid/status"),
+            (format!("tx/{txid}/outspend/3"), "tx/:txid/outspend/:vout"),
+            (
+                format!("block/{txid}/txs/25"),
+                "block/:hash/txs/:start_index",
+            ),
+            (format!("block/{txid}/txid/7"), "block/:hash/txid/:index"),
+            (
+                format!("address/bc1qxyz/txs/chain/{txid}"),
+                "address/:address/txs/chain/:last_seen_txid",
+            ),
+            ("blocks/tip/height".to_string(), "blocks/tip/height"),
+            ("blocks/840000".to_string(), "blocks/:start_height"),
+            ("block-height/840000".to_string(), "block-height/:height"),
+            ("tx".to_string(), "tx"),
+            (
+                "waterfalls/v2/waterfalls".to_string(),
+                "waterfalls/v2/waterfalls",
+            ),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(endpoint(&path), expected, "{path}");
+        }
+    }
+
+    fn credits(remaining: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            CREDITS_REMAINING_HEADER,
+            HeaderValue::from_str(remaining).unwrap(),
+        );
+        headers
+    }
+
+    const A: &str = "https://a.example/api/";
+    const B: &str = "https://b.example/api/";
+
+    #[test]
+    fn counts_calls_and_estimates_credits() {
+        let usage = UsageTracker::default();
+        usage.record(
+            (A, "waterfalls/v2/waterfalls"),
+            StatusCode::OK,
+            &credits("990"),
+        );
+        usage.record((A, "tx/aa"), StatusCode::OK, &HeaderMap::new());
+        usage.record((B, "tx/bb"), StatusCode::NOT_FOUND, &HeaderMap::new());
+
+        let stats = usage.stats();
+        assert_eq!(
+            stats.credits_remaining,
+            BTreeMap::from([(A.to_string(), 990)])
+        );
+        assert_eq!((stats.calls, stats.estimated_credits), (3, 11));
+        assert_eq!(
+            stats.endpoints["tx/:txid"],
+            EndpointUsage {
+                calls: 2,
+                estimated_credits: 1
+            }
+        );
+        assert_eq!(
+            stats.endpoints["waterfalls/v2/waterfalls"].estimated_credits,
+            10
+        );
+    }
+
+    #[test]
+    fn alert_fires_once_per_crossing() {
+        let fired = Arc::new(AtomicU64::new(0));
+        let last = fired.clone();
+        let usage = UsageTracker::new(Some(CreditAlert::new(100).on_low(move |_, remaining| {
+            last.store(remaining, Ordering::SeqCst);
+        })));
+        let alerted = || usage.lock().alerted.contains(A);
+
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("150"));
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("99"));
+        assert_eq!(fired.load(Ordering::SeqCst), 99);
+        assert!(alerted());
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("98"));
+        assert_eq!(
+            fired.load(Ordering::SeqCst),
+            99,
+            "no repeat while still low"
+        );
+        usage.record((A, "tx/a"), StatusCode::OK, &HeaderMap::new());
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("5000"));
+        assert!(!alerted(), "re-armed after a top-up");
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("50"));
+        assert_eq!(fired.load(Ordering::SeqCst), 50);
+    }
+
+    #[test]
+    fn each_backend_has_its_own_balance_and_alert() {
+        let fired = Arc::new(Mutex::new(Vec::new()));
+        let seen = fired.clone();
+        let usage = UsageTracker::new(Some(CreditAlert::new(100).on_low(
+            move |backend, remaining| {
+                seen.lock().unwrap().push((backend.to_string(), remaining));
+            },
+        )));
+
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("50"));
+        // Another account's healthy balance neither hides nor re-arms A's.
+        usage.record((B, "tx/a"), StatusCode::OK, &credits("9000"));
+        usage.record((A, "tx/a"), StatusCode::OK, &credits("40"));
+        usage.record((B, "tx/a"), StatusCode::OK, &credits("80"));
+
+        assert_eq!(
+            *fired.lock().unwrap(),
+            [(A.to_string(), 50), (B.to_string(), 80)]
+        );
+        assert_eq!(
+            usage.stats().credits_remaining,
+            BTreeMap::from([(A.to_string(), 40), (B.to_string(), 80)])
+        );
+    }
+}
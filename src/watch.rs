@@ -0,0 +1,360 @@
+//! [`TxWatcher`]: follow a set of transactions until they are buried or gone.
+//!
+//! Each [`TxWatcher::poll`] checks every watched txid with the batch lookups
+//! (so at most [`crate::ClientBuilder::batch_concurrency`] requests are in
+//! flight) plus one tip-height request, and reports what changed as
+//! [`TxEvent`]s. Transactions not yet confirmed are fetched in full, since
+//! only a `404` on `tx/:txid` tells "not in the mempool" apart from
+//! "unconfirmed"; confirmed ones only need the cheaper `tx/:txid/status`.
+//!
+//! A txid stops being watched once it reaches the target depth or is
+//! dropped.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use tracing::debug;
+
+use crate::{Client, Error, TxStatus};
+
+/// Default confirmation depth at which a transaction counts as final.
+pub const DEFAULT_CONFIRMATIONS: u32 = 6;
+
+/// Default time a transaction may be missing from both mempool and chain
+/// before it is reported as [`TxEvent::Dropped`].
+pub const DEFAULT_DROP_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// A change in a watched transaction's state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TxEvent {
+    /// The transaction was found unconfirmed (in the mempool).
+    Seen {
+        /// The transaction ID.
+        txid: String,
+    },
+    /// The transaction was included in a block.
+    Confirmed {
+        /// The transaction ID.
+        txid: String,
+        /// The including block's height.
+        height: u64,
+        /// The including block's hash.
+        block_hash: String,
+    },
+    /// The transaction reached the watcher's target depth and is no longer
+    /// watched.
+    ReachedDepth {
+        /// The transaction ID.
+        txid: String,
+        /// The confirmation count when this was noticed (at least the target).
+        confirmations: u32,
+    },
+    /// The block that confirmed the transaction left the best chain. The
+    /// transaction is watched again from the mempool.
+    Reorged {
+        /// The transaction ID.
+        txid: String,
+        /// The height of the block it was confirmed in.
+        height: u64,
+        /// The hash of the block it was confirmed in.
+        block_hash: String,
+    },
+    /// The transaction was neither confirmed nor in the mempool for the
+    /// whole drop timeout, and is no longer watched.
+    Dropped {
+        /// The transaction ID.
+        txid: String,
+    },
+}
+
+#[derive(Debug)]
+struct Watched {
+    txid: String,
+    seen: bool,
+    confirmed: Option<(u64, String)>,
+    /// When the transaction was last found missing with no sighting since;
+    /// starts at the time it was added.
+    missing_since: Option<Instant>,
+}
+
+impl Watched {
+    /// Applies one observation, pushing events. Returns whether the
+    /// transaction is finished (final or dropped).
+    fn advance(
+        &mut self,
+        observed: Observed,
+        policy: (u32, Duration),
+        tip_height: Option<u64>,
+        now: Instant,
+        events: &mut Vec<TxEvent>,
+    ) -> bool {
+        let (target, drop_timeout) = policy;
+        let txid = self.txid.clone();
+        let reorged = |events: &mut Vec<TxEvent>, (height, block_hash): (u64, String)| {
+            debug!(target: "esplora_rs", "tx {} reorged out of block {}", txid, block_hash);
+            events.push(TxEvent::Reorged {
+                txid: txid.clone(),
+                height,
+                block_hash,
+            });
+        };
+        match observed {
+            Observed::Confirmed(height, block_hash) => {
+                self.seen = true;
+                self.missing_since = None;
+                if self.confirmed.as_ref().map(|(_, h)| h) != Some(&block_hash) {
+                    if let Some(old) = self.confirmed.take() {
+                        reorged(events, old);
+                    }
+                    events.push(TxEvent::Confirmed {
+                        txid: txid.clone(),
+                        height,
+                        block_hash: block_hash.clone(),
+                    });
+                    self.confirmed = Some((height, block_hash));
+                }
+                let tip = tip_height.unwrap_or(height);
+                let confirmations =
+                    u32::try_from(tip.saturating_sub(height) + 1).unwrap_or(u32::MAX);
+                if confirmations >= target {
+                    events.push(TxEvent::ReachedDepth {
+                        txid: txid.clone(),
+                        confirmations,
+                    });
+                    return true;
+                }
+            }
+            Observed::Unconfirmed => {
+                if let Some(old) = self.confirmed.take() {
+                    reorged(events, old);
+                }
+                if !self.seen {
+                    events.push(TxEvent::Seen { txid: txid.clone() });
+                    self.seen = true;
+                }
+                self.missing_since = None;
+            }
+            Observed::Missing => {
+                if let Some(old) = self.confirmed.take() {
+                    reorged(events, old);
+                }
+                let since = *self.missing_since.get_or_insert(now);
+                if now.duration_since(since) >= drop_timeout {
+                    events.push(TxEvent::Dropped { txid: txid.clone() });
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// What one poll found for a transaction.
+enum Observed {
+    Missing,
+    Unconfirmed,
+    Confirmed(u64, String),
+}
+
+impl Observed {
+    fn from_status(status: &TxStatus) -> Self {
+        match (status.confirmed, status.block_height, &status.block_hash) {
+            (true, Some(height), Some(hash)) => Self::Confirmed(height, hash.clone()),
+            _ => Self::Unconfirmed,
+        }
+    }
+
+    fn from_result<T>(
+        result: Result<T, Error>,
+        status: impl Fn(&T) -> &TxStatus,
+    ) -> Result<Self, Error> {
+        match result {
+            Ok(value) => Ok(Self::from_status(status(&value))),
+            Err(err) if err.status() == Some(404) => Ok(Self::Missing),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Tracks transactions from broadcast to a configurable confirmation depth.
+///
+/// ```no_run
+/// # async fn demo(client: esplora_rs::Client, txid: String) -> Result<(), esplora_rs::Error> {
+/// use std::time::Duration;
+/// use esplora_rs::{TxEvent, TxWatcher};
+/// use futures::StreamExt;
+///
+/// let mut watcher = TxWatcher::new(client).confirmations(3);
+/// watcher.watch(&txid);
+/// let mut events = watcher.into_stream(Duration::from_secs(30));
+/// while let Some(event) = events.next().await {
+///     match event? {
+///         TxEvent::ReachedDepth { txid, .. } => println!("{txid} is final"),
+///         other => println!("{other:?}"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TxWatcher {
+    client: Client,
+    confirmations: u32,
+    drop_timeout: Duration,
+    watched: Vec<Watched>,
+}
+
+impl TxWatcher {
+    /// A watcher with no transactions, [`DEFAULT_CONFIRMATIONS`] and
+    /// [`DEFAULT_DROP_TIMEOUT`].
+    pub fn new(client: Client) -> Self {
+        Self {
+            client: client.following_chain(),
+            confirmations: DEFAULT_CONFIRMATIONS,
+            drop_timeout: DEFAULT_DROP_TIMEOUT,
+            watched: Vec::new(),
+        }
+    }
+
+    /// Sets the depth at which a transaction is reported as
+    /// [`TxEvent::ReachedDepth`] and no longer watched. `0` is treated as `1`.
+    #[must_use]
+    pub fn confirmations(mut self, confirmations: u32) -> Self {
+        self.confirmations = confirmations.max(1);
+        self
+    }
+
+    /// Sets how long a transaction may be missing before it is reported as
+    /// [`TxEvent::Dropped`].
+    #[must_use]
+    pub fn drop_timeout(mut self, timeout: Duration) -> Self {
+        self.drop_timeout = timeout;
+        self
+    }
+
+    /// Starts watching `txid`. Watching a txid twice has no effect.
+    pub fn watch(&mut self, txid: &str) {
+        if !self.watched.iter().any(|w| w.txid == txid) {
+            self.watched.push(Watched {
+                txid: txid.to_string(),
+                seen: false,
+                confirmed: None,
+                missing_since: Some(Instant::now()),
+            });
+        }
+    }
+
+    /// Stops watching `txid`, returning whether it was watched.
+    pub fn unwatch(&mut self, txid: &str) -> bool {
+        let before = self.watched.len();
+        self.watched.retain(|w| w.txid != txid);
+        self.watched.len() != before
+    }
+
+    /// The txids still being watched, in the order they were added.
+    pub fn watching(&self) -> impl Iterator<Item = &str> {
+        self.watched.iter().map(|w| w.txid.as_str())
+    }
+
+    /// Checks every watched transaction once and returns what changed.
+    ///
+    /// # Errors
+    /// Returns the first request error other than a `404`. Nothing is
+    /// updated in that case, so the next poll reports the same transitions.
+    pub async fn poll(&mut self) -> Result<Vec<TxEvent>, Error> {
+        let (confirmed, pending): (Vec<&Watched>, Vec<&Watched>) =
+            self.watched.iter().partition(|w| w.confirmed.is_some());
+        let confirmed: Vec<String> = confirmed.iter().map(|w| w.txid.clone()).collect();
+        let pending: Vec<String> = pending.iter().map(|w| w.txid.clone()).collect();
+
+        let mut observed = Vec::with_capacity(self.watched.len());
+        for (txid, status) in confirmed
+            .iter()
+            .zip(self.client.get_tx_statuses(&confirmed).await)
+        {
+            observed.push((txid, Observed::from_result(status, |s| s)?));
+        }
+        for (txid, tx) in pending.iter().zip(self.client.get_txs(&pending).await) {
+            observed.push((txid, Observed::from_result(tx, |tx| &tx.status)?));
+        }
+        let tip_height = if observed
+            .iter()
+            .any(|(_, o)| matches!(o, Observed::Confirmed(..)))
+        {
+            Some(self.client.get_tip_height().await?)
+        } else {
+            None
+        };
+
+        let now = Instant::now();
+        let policy = (self.confirmations, self.drop_timeout);
+        let mut events = Vec::new();
+        let mut finished = Vec::new();
+        for (txid, observed) in observed {
+            let watched = self
+                .watched
+                .iter_mut()
+                .find(|w| &w.txid == txid)
+                .expect("observed txids are watched");
+            if watched.advance(observed, policy, tip_height, now, &mut events) {
+                finished.push(txid.clone());
+            }
+        }
+        self.watched.retain(|w| !finished.contains(&w.txid));
+        Ok(events)
+    }
+
+    /// Polls every `poll_interval` (the first poll is immediate), yielding
+    /// events as they happen. Request errors are yielded and polling
+    /// continues; the stream ends once nothing is left to watch.
+    pub fn into_stream(self, poll_interval: Duration) -> TxEvents {
+        let state = (self, VecDeque::new(), false);
+        let inner = stream::unfold(
+            state,
+            move |(mut watcher, mut pending, mut polled)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((Ok(event), (watcher, pending, polled)));
+                    }
+                    if watcher.watched.is_empty() {
+                        return None;
+                    }
+                    if polled {
+                        tokio::time::sleep(poll_interval).await;
+                    }
+                    polled = true;
+                    match watcher.poll().await {
+                        Ok(events) => pending.extend(events),
+                        Err(err) => return Some((Err(err), (watcher, pending, polled))),
+                    }
+                }
+            },
+        );
+        TxEvents {
+            inner: inner.boxed(),
+        }
+    }
+}
+
+/// The stream returned by [`TxWatcher::into_stream`].
+pub struct TxEvents {
+    inner: BoxStream<'static, Result<TxEvent, Error>>,
+}
+
+impl fmt::Debug for TxEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TxEvents").finish_non_exhaustive()
+    }
+}
+
+impl Stream for TxEvents {
+    type Item = Result<TxEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}